@@ -1,14 +1,104 @@
 use std::fmt::Display;
 
+use crate::source_map::FileId;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Program {
   Body { stmts: Vec<Statement> },
 }
 
+impl Program {
+  /// Returns the modules imported by the program.
+  pub fn imports(&self) -> impl Iterator<Item = &Identifier> {
+    let Program::Body { stmts } = self;
+
+    stmts.iter().filter_map(|statement| match statement {
+      Statement::Import { module } => Some(module),
+      _ => None,
+    })
+  }
+}
+
+/// A parsed source file, other modules import it by `name`: the name of the
+/// file without its extension.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Module {
+  pub name: String,
+  pub program: Program,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Statement {
-  Expr { expr: Expr },
-  Declare { identifier: Identifier, rhs: Expr },
+  Expr {
+    expr: Expr,
+  },
+  /// The type is inferred from `rhs` unless annotated, e.g. `let x: int = 1;`.
+  Declare {
+    identifier: Identifier,
+    type_annotation: Option<TypeAnnotation>,
+    rhs: Expr,
+    mutable: bool,
+  },
+  /// Plain (`op` is `None`) or compound assignment.
+  Assign {
+    identifier: Identifier,
+    op: Option<BinaryOp>,
+    rhs: Expr,
+  },
+  If {
+    condition: Expr,
+    then_body: Vec<Statement>,
+    else_body: Option<Vec<Statement>>,
+  },
+  While {
+    condition: Expr,
+    body: Vec<Statement>,
+  },
+  Break {
+    source_pos: SourcePos,
+  },
+  Continue {
+    source_pos: SourcePos,
+  },
+  /// `pub` functions can be called from the modules importing this one.
+  Function {
+    identifier: Identifier,
+    params: Vec<Parameter>,
+    return_type: Option<TypeAnnotation>,
+    body: Vec<Statement>,
+    public: bool,
+  },
+  /// `import math;`, only allowed at the top level.
+  Import {
+    module: Identifier,
+  },
+  Return {
+    expr: Option<Expr>,
+    source_pos: SourcePos,
+  },
+  /// A statement with a syntax error, already reported by the parser.
+  Error {
+    source_pos: SourcePos,
+  },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Parameter {
+  pub identifier: Identifier,
+  pub param_type: TypeAnnotation,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeAnnotation {
+  pub type_name: TypeName,
+  pub source_pos: SourcePos,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TypeName {
+  Int,
+  Bool,
+  String,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -19,8 +109,10 @@ pub struct Identifier {
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Expr {
+  /// Kept wider than `i32` so that out of range literals can be reported by
+  /// the binder.
   Int {
-    n: i32,
+    n: i64,
     source_pos: SourcePos,
   },
   Bool {
@@ -37,16 +129,33 @@ pub enum Expr {
     lhs: Box<Expr>,
     rhs: Box<Expr>,
   },
+  UnaryOp {
+    op: UnaryOp,
+    expr: Box<Expr>,
+  },
   ParenthesizedExpression {
     expr: Box<Expr>,
   },
+  /// `print`/`println`. If the first argument is a string literal it is used
+  /// as a format string with a `{}` placeholder for every other argument.
   Print {
-    expr: Box<Expr>,
+    args: Vec<Expr>,
+    newline: bool,
+    source_pos: SourcePos,
+  },
+  /// `module` is set for calls to functions of imported modules, e.g.
+  /// `math::square(2)`.
+  Call {
+    module: Option<Identifier>,
+    identifier: Identifier,
+    args: Vec<Expr>,
+    source_pos: SourcePos,
   },
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub struct SourcePos {
+  pub file: FileId,
   pub start: usize,
   pub end: usize,
 }
@@ -59,6 +168,12 @@ pub enum BinaryOp {
   Div { source_pos: SourcePos },
   And { source_pos: SourcePos },
   Or { source_pos: SourcePos },
+  Eq { source_pos: SourcePos },
+  Ne { source_pos: SourcePos },
+  Lt { source_pos: SourcePos },
+  Le { source_pos: SourcePos },
+  Gt { source_pos: SourcePos },
+  Ge { source_pos: SourcePos },
 }
 
 impl Display for BinaryOp {
@@ -70,6 +185,27 @@ impl Display for BinaryOp {
       BinaryOp::Div { source_pos: _ } => write!(f, "div"),
       BinaryOp::And { source_pos: _ } => write!(f, "and"),
       BinaryOp::Or { source_pos: _ } => write!(f, "or"),
+      BinaryOp::Eq { source_pos: _ } => write!(f, "eq"),
+      BinaryOp::Ne { source_pos: _ } => write!(f, "ne"),
+      BinaryOp::Lt { source_pos: _ } => write!(f, "lt"),
+      BinaryOp::Le { source_pos: _ } => write!(f, "le"),
+      BinaryOp::Gt { source_pos: _ } => write!(f, "gt"),
+      BinaryOp::Ge { source_pos: _ } => write!(f, "ge"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UnaryOp {
+  Neg { source_pos: SourcePos },
+  Not { source_pos: SourcePos },
+}
+
+impl Display for UnaryOp {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    match self {
+      UnaryOp::Neg { source_pos: _ } => write!(f, "neg"),
+      UnaryOp::Not { source_pos: _ } => write!(f, "not"),
     }
   }
 }
@@ -1,12 +1,51 @@
-use crate::{ast::*, bind::bound_ast::Type};
+use std::collections::{HashMap, HashSet};
 
-use super::bound_ast::{
-  binary_to_bound_binary_op, BoundExpr, BoundProgram, BoundStatement, ProgramError, TypeError,
+use crate::{
+  ast::*,
+  bind::bound_ast::Type,
+  code_gen::llvm_context::generated_function_names,
+  diagnostic::{Diagnostic, DiagnosticCode},
 };
 
-#[derive(Default)]
+use super::{
+  bound_ast::{
+    binary_to_bound_binary_op, unary_to_bound_unary_op, BoundExpr, BoundProgram, BoundStatement,
+    ProgramError, Symbol,
+  },
+  symbol_table::SymbolTable,
+};
+
+/// Cloning a binder snapshots everything declared so far.
+#[derive(Clone, Default)]
 pub struct Binder {
-  bound_exprs: Vec<(String, BoundExpr)>,
+  symbol_table: SymbolTable,
+  /// The functions of the module being bound.
+  functions: HashMap<String, FunctionSignature>,
+  /// The functions of every imported module bound so far, by module name.
+  modules: HashMap<String, HashMap<String, FunctionSignature>>,
+  /// The modules imported by the module being bound.
+  imports: HashSet<String>,
+  /// The name of the imported module being bound, `None` for the entry module.
+  module: Option<String>,
+  /// How many loops deep the statement currently being bound is.
+  loop_depth: usize,
+  /// The return type of the function whose body is currently being bound.
+  return_type: Option<Type>,
+  /// How many statements were skipped because of syntax errors.
+  syntax_errors: usize,
+}
+
+#[derive(Clone, Debug)]
+struct FunctionSignature {
+  /// The name used in the bound program, prefixed with `mod.` and the module's
+  /// name for functions of imported modules, e.g. `mod.math.square`. The fixed
+  /// prefix keeps modules such as `llvm` from producing the names LLVM
+  /// reserves for its intrinsics.
+  name: String,
+  public: bool,
+  param_types: Vec<Type>,
+  return_type: Type,
+  source_pos: SourcePos,
 }
 
 impl Binder {
@@ -14,208 +53,1009 @@ impl Binder {
     Self::default()
   }
 
-  /// Binds the program.
-  pub fn bind(&mut self, prog: &Program) -> Result<BoundProgram, ProgramError> {
-    match prog {
-      Program::Body { stmts } => {
-        let mut bound_statements = Vec::<BoundStatement>::new();
-        let mut expr_errors = Vec::<TypeError>::new();
-
-        for statement in stmts {
-          match statement {
-            Statement::Expr { expr } => match self.bind_expr(expr) {
-              Ok(expr) => bound_statements.push(BoundStatement::BoundExpr { expr }),
-              Err(err) => expr_errors.push(err),
-            },
-            Statement::Declare { identifier, rhs } => {
-              let mut bound_names = self.bound_exprs.iter().map(|x| x.to_owned().0);
-              if bound_names.any(|x| x == identifier.text) {
-                let err = TypeError {
-                  msg: "Variable identifier is already taken".to_owned(),
-                  source_pos: SourcePos {
-                    start: identifier.source_pos.start,
-                    end: identifier.source_pos.end,
-                  },
-                };
+  /// Binds the first module along with the ones it imports, which are all
+  /// linked into a single program. The top level statements of a module run
+  /// before the ones of the first module importing it.
+  pub fn bind_modules(&mut self, modules: &[Module]) -> Result<BoundProgram, ProgramError> {
+    let mut expr_errors = Vec::<Diagnostic>::new();
+    let bound_statements = match modules.first() {
+      Some(entry) => self.bind_program(
+        &entry.program,
+        modules,
+        &mut vec![entry.name.to_owned()],
+        &mut expr_errors,
+      ),
+      None => Vec::new(),
+    };
 
-                expr_errors.push(err);
-                break;
-              }
+    if expr_errors.is_empty() {
+      Ok(BoundProgram::Body {
+        stmts: bound_statements,
+      })
+    } else {
+      Err(ProgramError { expr_errors })
+    }
+  }
 
-              match self.bind_expr(rhs) {
-                Ok(rhs) => {
-                  self
-                    .bound_exprs
-                    .push((identifier.to_owned().text, rhs.to_owned()));
-                  let expr = BoundExpr::BoundDeclaration {
-                    identifier: identifier.to_owned().text,
-                    value_type: Self::get_type(&rhs),
-                    rhs: Box::new(rhs),
-                  };
-                  bound_statements.push(BoundStatement::BoundExpr { expr })
-                }
-                Err(err) => expr_errors.push(err),
-              }
-            }
+  /// Binds the modules imported by the program that have not been bound yet,
+  /// followed by the program itself. `importing` holds the names of the
+  /// modules whose imports are being bound, in order, to detect import cycles.
+  fn bind_program(
+    &mut self,
+    prog: &Program,
+    modules: &[Module],
+    importing: &mut Vec<String>,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Vec<BoundStatement> {
+    let mut bound_statements = Vec::<BoundStatement>::new();
+
+    for import in prog.imports() {
+      if let Some(cycle_start) = importing.iter().position(|name| *name == import.text) {
+        let cycle = importing[cycle_start..]
+          .iter()
+          .chain(std::iter::once(&import.text))
+          .map(String::as_str)
+          .collect::<Vec<&str>>()
+          .join(" -> ");
+
+        expr_errors.push(
+          Diagnostic::error(
+            DiagnosticCode::ImportCycle,
+            format!("Import cycle: {}.", cycle),
+            import.source_pos,
+          )
+          .with_primary_label(format!("'{}' is already being imported", import.text)),
+        );
+        self.imports.insert(import.text.to_owned());
+        continue;
+      }
+
+      if !self.modules.contains_key(&import.text) {
+        match modules.iter().find(|module| module.name == import.text) {
+          Some(module) => {
+            let module_statements = self.bind_module(module, modules, importing, expr_errors);
+            bound_statements.extend(module_statements);
+          }
+          None => {
+            expr_errors.push(Diagnostic::error(
+              DiagnosticCode::UnknownModule,
+              format!("Cannot find module '{}'.", import.text),
+              import.source_pos,
+            ));
+            continue;
           }
         }
+      }
+
+      self.imports.insert(import.text.to_owned());
+    }
+
+    let Program::Body { stmts } = prog;
+    self.declare_functions(stmts, expr_errors);
+    bound_statements.extend(self.bind_statements(stmts, expr_errors));
+
+    bound_statements
+  }
+
+  /// Binds an imported module on its own, nothing declared by the module
+  /// importing it is visible.
+  fn bind_module(
+    &mut self,
+    module: &Module,
+    modules: &[Module],
+    importing: &mut Vec<String>,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Vec<BoundStatement> {
+    let outer_scopes = self.symbol_table.isolate();
+    let outer_functions = std::mem::take(&mut self.functions);
+    let outer_imports = std::mem::take(&mut self.imports);
+    let outer_module = self.module.replace(module.name.to_owned());
+
+    importing.push(module.name.to_owned());
+    let bound_statements = self.bind_program(&module.program, modules, importing, expr_errors);
+    importing.pop();
+
+    let functions = std::mem::replace(&mut self.functions, outer_functions);
+    self.modules.insert(module.name.to_owned(), functions);
+    self.imports = outer_imports;
+    self.module = outer_module;
+    self.symbol_table.restore(outer_scopes);
 
-        if expr_errors.is_empty() {
-          Ok(BoundProgram::Body {
-            stmts: bound_statements,
-          })
-        } else {
-          Err(ProgramError { expr_errors })
+    bound_statements
+  }
+
+  /// Registers the signatures of all the passed top level functions so they can
+  /// be called before they are declared (or recursively).
+  fn declare_functions(&mut self, stmts: &[Statement], expr_errors: &mut Vec<Diagnostic>) {
+    for statement in stmts {
+      if let Statement::Function {
+        identifier,
+        params,
+        return_type,
+        body: _,
+        public,
+      } = statement
+      {
+        if let Some(existing) = self.functions.get(&identifier.text) {
+          expr_errors.push(
+            Diagnostic::error(
+              DiagnosticCode::DuplicateFunction,
+              "Function identifier is already taken",
+              identifier.source_pos,
+            )
+            .with_primary_label(format!("'{}' redeclared here", identifier.text))
+            .with_label(existing.source_pos, "first declared here"),
+          );
+          continue;
+        }
+
+        let name = match &self.module {
+          Some(module) => format!("mod.{}.{}", module, identifier.text),
+          None => identifier.text.to_owned(),
+        };
+
+        // Would clash with the functions the code generator adds. It is still
+        // declared so calls to it are not reported as undefined.
+        let reserved = generated_function_names().contains(&name);
+        if reserved {
+          expr_errors.push(Diagnostic::error(
+            DiagnosticCode::ReservedFunctionName,
+            format!("'{}' is a reserved function name.", identifier.text),
+            identifier.source_pos,
+          ));
         }
+
+        let signature = FunctionSignature {
+          name,
+          public: *public,
+          param_types: params
+            .iter()
+            .map(|param| param.param_type.type_name.into())
+            .collect(),
+          return_type: match return_type {
+            _ if reserved => Type::Error,
+            Some(return_type) => return_type.type_name.into(),
+            None => Type::Void,
+          },
+          source_pos: identifier.source_pos,
+        };
+        self.functions.insert(identifier.text.to_owned(), signature);
       }
     }
   }
 
-  /// Tries to bind an Expression.
-  pub fn bind_expr(&mut self, expr: &Expr) -> Result<BoundExpr, TypeError> {
-    match expr {
-      Expr::Int { n, source_pos: _ } => Ok(BoundExpr::Int { n: n.to_owned() }),
-      Expr::Bool { b, source_pos: _ } => Ok(BoundExpr::Bool { b: b.to_owned() }),
-      Expr::ParenthesizedExpression { expr } => self.bind_expr(expr),
-      Expr::BinaryOp { op, lhs, rhs } => {
-        let source_pos = Self::get_src_pos_bin_op(op);
-        let lhs_expr = self.bind_expr(lhs);
-        let rhs_expr = self.bind_expr(rhs);
-
-        match (lhs_expr, rhs_expr) {
-          (Ok(lhs_expr), Ok(rhs_expr)) => {
-            let lhs_type = Self::get_type(&lhs_expr);
-            let rhs_type = Self::get_type(&rhs_expr);
-            let (in_type, bin_op_type) = Self::get_op_type(op);
-
-            let types_are_correct = lhs_type == rhs_type && lhs_type == in_type;
-
-            match types_are_correct {
-              true => {
-                let lhs = Box::new(lhs_expr);
-                let rhs = Box::new(rhs_expr);
-
-                let tmp = BoundExpr::BoundBinaryOp {
-                  op: binary_to_bound_binary_op(*op),
-                  lhs,
-                  rhs,
-                  bin_op_type,
-                };
+  /// Binds a list of statements, pushing any errors encountered to `expr_errors`.
+  fn bind_statements(
+    &mut self,
+    stmts: &[Statement],
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Vec<BoundStatement> {
+    let mut bound_statements = Vec::<BoundStatement>::new();
+
+    for statement in stmts {
+      match statement {
+        Statement::Expr { expr } => {
+          let expr = self.bind_expr(expr, expr_errors);
+          bound_statements.push(BoundStatement::BoundExpr { expr })
+        }
+        Statement::Declare {
+          identifier,
+          type_annotation,
+          rhs: rhs_expr,
+          mutable,
+        } => {
+          // The variable is not in scope yet while its value is being bound.
+          // If that fails it is still declared, with the error type, so that
+          // its uses are not reported as well.
+          let rhs = self.bind_expr(rhs_expr, expr_errors);
+          let rhs = match Self::get_type(&rhs) {
+            Type::Void => {
+              expr_errors.push(Diagnostic::error(
+                DiagnosticCode::VoidValue,
+                "Cannot assign a value of type Void.",
+                Self::get_src_pos(rhs_expr),
+              ));
+              None
+            }
+            _ => Some(rhs),
+          };
+          // An annotated variable keeps its type even if its value is invalid.
+          let (rhs, value_type) = match (type_annotation, rhs) {
+            (Some(type_annotation), rhs) => {
+              let expected = Type::from(type_annotation.type_name);
+              let rhs = rhs.filter(|rhs| {
+                let found = Self::get_type(rhs);
+                let types_match = Self::types_match(&expected, &found);
+
+                if !types_match {
+                  expr_errors.push(
+                    Diagnostic::error(
+                      DiagnosticCode::MismatchedTypes,
+                      format!("Expected value of type {:?}, found {:?}.", expected, found),
+                      Self::get_src_pos(rhs_expr),
+                    )
+                    .with_primary_label(format!("expected {:?}, found {:?}", expected, found))
+                    .with_label(type_annotation.source_pos, "expected due to this"),
+                  );
+                }
+
+                types_match
+              });
 
-                Ok(tmp)
+              (rhs, expected)
+            }
+            (None, rhs) => {
+              let rhs_type = rhs.as_ref().map_or(Type::Error, Self::get_type);
+              (rhs, rhs_type)
+            }
+          };
+
+          match self.symbol_table.declare(
+            &identifier.text,
+            value_type,
+            *mutable,
+            identifier.source_pos,
+          ) {
+            Ok(symbol) => {
+              if let Some(rhs) = rhs {
+                let expr = BoundExpr::BoundDeclaration {
+                  symbol,
+                  rhs: Box::new(rhs),
+                };
+                bound_statements.push(BoundStatement::BoundExpr { expr })
               }
-              false => Self::invalid_op_err(&lhs_type, &rhs_type, op, source_pos),
+            }
+            Err(existing) => {
+              let err = Diagnostic::error(
+                DiagnosticCode::DuplicateVariable,
+                "Variable identifier is already taken",
+                identifier.source_pos,
+              )
+              .with_primary_label(format!("'{}' redeclared here", identifier.text))
+              .with_label(existing.source_pos, "first declared here");
+
+              expr_errors.push(err);
             }
           }
-          (Ok(_), Err(err)) => Err(err),
-          (Err(err), Ok(_)) => Err(err),
-          (Err(err), Err(_)) => Err(err),
         }
-      }
-      Expr::Print { expr } => match *expr.to_owned() {
-        Expr::Int { n, source_pos: _ } => Ok(BoundExpr::BoundPrint {
-          expr_type: Type::Int,
-          expr: Box::new(BoundExpr::Int { n }),
-        }),
-        Expr::Bool {
-          b: _,
-          source_pos: _,
-        } => todo!(),
-        Expr::String {
-          text: str,
-          source_pos: _,
-        } => Ok(BoundExpr::BoundPrint {
-          expr_type: Type::Int,
-          expr: Box::new(BoundExpr::String { str }),
-        }),
-        Expr::BinaryOp { op, lhs, rhs } => {
-          let source_pos = Self::get_src_pos(&Expr::BinaryOp { op, lhs, rhs });
-          Err(TypeError {
-            msg: "Can only print literals".to_owned(),
-            source_pos,
-          })
+        Statement::If {
+          condition,
+          then_body,
+          else_body,
+        } => {
+          let condition = self.bind_condition(condition, expr_errors);
+          let then_body = self.bind_block(then_body, expr_errors);
+          let else_body = else_body
+            .as_ref()
+            .map(|else_body| self.bind_block(else_body, expr_errors));
+
+          match condition {
+            Ok(condition) => bound_statements.push(BoundStatement::BoundIf {
+              condition,
+              then_body,
+              else_body,
+            }),
+            Err(err) => expr_errors.push(err),
+          }
+        }
+        Statement::While { condition, body } => {
+          let condition = self.bind_condition(condition, expr_errors);
+
+          self.loop_depth += 1;
+          let body = self.bind_block(body, expr_errors);
+          self.loop_depth -= 1;
+
+          match condition {
+            Ok(condition) => bound_statements.push(BoundStatement::BoundWhile { condition, body }),
+            Err(err) => expr_errors.push(err),
+          }
         }
-        Expr::ParenthesizedExpression { expr } => {
-          let source_pos = Self::get_src_pos(&expr);
-          Err(TypeError {
-            msg: "Can only print literals".to_owned(),
-            source_pos,
-          })
+        Statement::Break { source_pos } => match self.loop_depth {
+          0 => expr_errors.push(Self::outside_loop_err("break", source_pos)),
+          _ => bound_statements.push(BoundStatement::BoundBreak {
+            source_pos: *source_pos,
+          }),
+        },
+        Statement::Continue { source_pos } => match self.loop_depth {
+          0 => expr_errors.push(Self::outside_loop_err("continue", source_pos)),
+          _ => bound_statements.push(BoundStatement::BoundContinue {
+            source_pos: *source_pos,
+          }),
+        },
+        Statement::Function {
+          identifier,
+          params,
+          return_type: _,
+          body,
+          public: _,
+        } => {
+          if let Some(function) = self.bind_function(identifier, params, body, expr_errors) {
+            bound_statements.push(function)
+          }
         }
-        Expr::Print { expr } => {
-          let source_pos = Self::get_src_pos(&expr);
-          Err(TypeError {
-            msg: "Can only print literals".to_owned(),
-            source_pos,
-          })
+        Statement::Return { expr, source_pos } => {
+          if let Some(statement) = self.bind_return(expr, source_pos, expr_errors) {
+            bound_statements.push(statement)
+          }
         }
-        Expr::Identifier(identifier) => match self.retrieve_variable(&identifier) {
-          Ok(expr) => {
-            let value_type = Self::get_type(&expr);
-            let expr = Box::new(expr);
-
-            Ok(BoundExpr::BoundPrint {
-              expr_type: value_type,
-              expr,
-            })
+        Statement::Assign {
+          identifier,
+          op,
+          rhs,
+        } => {
+          if let Some(statement) = self.bind_assignment(identifier, op, rhs, expr_errors) {
+            bound_statements.push(statement)
           }
-          e @ Err(_) => e,
-        },
-      },
+        }
+        // Bound by `Self::bind_program` before the rest of the module.
+        Statement::Import { module: _ } => {}
+        // Reported by the parser.
+        Statement::Error { source_pos: _ } => self.syntax_errors += 1,
+      }
+    }
+
+    bound_statements
+  }
+
+  /// Binds the statements of a nested block in their own scope.
+  fn bind_block(
+    &mut self,
+    stmts: &[Statement],
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Vec<BoundStatement> {
+    self.symbol_table.push_scope();
+    let bound_statements = self.bind_statements(stmts, expr_errors);
+    self.symbol_table.pop_scope();
+
+    bound_statements
+  }
+
+  /// Binds the body of a function previously registered by `Self::declare_functions`.
+  /// Function bodies can only see their own parameters.
+  fn bind_function(
+    &mut self,
+    identifier: &Identifier,
+    params: &[Parameter],
+    body: &[Statement],
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Option<BoundStatement> {
+    if !self.symbol_table.is_global_scope() {
+      expr_errors.push(Diagnostic::error(
+        DiagnosticCode::NestedFunction,
+        "Functions can only be declared at the top level.",
+        identifier.source_pos,
+      ));
+      return None;
+    }
+
+    // Reserved or duplicate functions are reported by `Self::declare_functions`,
+    // reserved ones are declared with the error type.
+    let signature = match self.functions.get(&identifier.text) {
+      Some(signature)
+        if signature.source_pos == identifier.source_pos
+          && signature.return_type != Type::Error =>
+      {
+        signature.to_owned()
+      }
+      _ => return None,
+    };
+
+    let outer_scopes = self.symbol_table.isolate();
+    let mut bound_params = Vec::<Symbol>::new();
+
+    for (param, param_type) in params.iter().zip(signature.param_types) {
+      match self.symbol_table.declare(
+        &param.identifier.text,
+        param_type,
+        false,
+        param.identifier.source_pos,
+      ) {
+        Ok(symbol) => bound_params.push(symbol),
+        Err(existing) => expr_errors.push(
+          Diagnostic::error(
+            DiagnosticCode::DuplicateParameter,
+            "Parameter identifier is already taken",
+            param.identifier.source_pos,
+          )
+          .with_label(existing.source_pos, "first declared here"),
+        ),
+      }
+    }
+
+    let error_count = expr_errors.len() + self.syntax_errors;
+    self.return_type = Some(signature.return_type.to_owned());
+    let body = self.bind_block(body, expr_errors);
+    self.return_type = None;
+    self.symbol_table.restore(outer_scopes);
+
+    // A return statement with errors is missing from the bound body.
+    let body_has_errors = expr_errors.len() + self.syntax_errors > error_count;
+    if signature.return_type != Type::Void && !body_has_errors && !Self::always_returns(&body) {
+      expr_errors.push(Diagnostic::error(
+        DiagnosticCode::MissingReturn,
+        format!(
+          "Function '{}' does not return a value on every path.",
+          identifier.text
+        ),
+        identifier.source_pos,
+      ));
+    }
+
+    Some(BoundStatement::BoundFunction {
+      identifier: signature.name,
+      params: bound_params,
+      return_type: signature.return_type,
+      body,
+      source_pos: identifier.source_pos,
+    })
+  }
+
+  /// Binds a return statement, making sure it matches the return type of the
+  /// enclosing function.
+  fn bind_return(
+    &mut self,
+    expr: &Option<Expr>,
+    source_pos: &SourcePos,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Option<BoundStatement> {
+    let return_type = self.return_type.to_owned();
+    if return_type.is_none() {
+      expr_errors.push(Diagnostic::error(
+        DiagnosticCode::ReturnOutsideFunction,
+        "'return' can only be used inside of a function.",
+        source_pos.to_owned(),
+      ));
+    }
+
+    let bound_expr = expr.as_ref().map(|expr| self.bind_expr(expr, expr_errors));
+    let return_type = return_type?;
+    let found_type = bound_expr.as_ref().map_or(Type::Void, Self::get_type);
+
+    if !Self::types_match(&return_type, &found_type) {
+      let source_pos = expr.as_ref().map_or(*source_pos, Self::get_src_pos);
+      expr_errors.push(Diagnostic::error(
+        DiagnosticCode::MismatchedTypes,
+        format!(
+          "Expected return value of type {:?}, found {:?}.",
+          return_type, found_type
+        ),
+        source_pos,
+      ));
+      return None;
+    }
+
+    Some(BoundStatement::BoundReturn {
+      expr: bound_expr,
+      source_pos: *source_pos,
+    })
+  }
+
+  /// Binds an assignment to a mutable variable. Compound assignments such as
+  /// `x += 1` are bound as `x = x + 1`.
+  fn bind_assignment(
+    &mut self,
+    identifier: &Identifier,
+    op: &Option<BinaryOp>,
+    rhs: &Expr,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Option<BoundStatement> {
+    let symbol = self.symbol_table.lookup(&identifier.text).cloned();
+    match &symbol {
+      None => expr_errors.push(Diagnostic::error(
+        DiagnosticCode::UndefinedVariable,
+        "Variable is undefined",
+        identifier.source_pos,
+      )),
+      Some(symbol) if !symbol.mutable => expr_errors.push(
+        Diagnostic::error(
+          DiagnosticCode::ImmutableAssignment,
+          format!(
+            "Cannot assign twice to immutable variable '{}'.",
+            symbol.name
+          ),
+          identifier.source_pos,
+        )
+        .with_label(symbol.source_pos, "first assignment")
+        .with_help(format!(
+          "consider making it mutable: 'let mut {}'",
+          symbol.name
+        )),
+      ),
+      Some(_) => {}
+    }
+
+    // The value is bound even if the variable cannot be assigned to, to report
+    // its errors as well.
+    let bound_rhs = self.bind_expr(rhs, expr_errors);
+    let symbol = symbol.filter(|symbol| symbol.mutable)?;
+    let bound_rhs = match op {
+      Some(op) => {
+        let rhs_type = Self::get_type(&bound_rhs);
+        let bin_op_type = match Self::get_op_type(op, &symbol.value_type) {
+          _ if symbol.value_type == Type::Error || rhs_type == Type::Error => Type::Error,
+          Some(bin_op_type) if rhs_type == symbol.value_type => bin_op_type,
+          _ => {
+            expr_errors.push(Self::invalid_op_err(
+              &symbol.value_type,
+              &rhs_type,
+              op,
+              Self::get_src_pos_bin_op(op),
+            ));
+            return None;
+          }
+        };
+
+        BoundExpr::BoundBinaryOp {
+          op: binary_to_bound_binary_op(*op),
+          lhs: Box::new(BoundExpr::BoundVariable {
+            symbol: symbol.to_owned(),
+            source_pos: identifier.source_pos,
+          }),
+          rhs: Box::new(bound_rhs),
+          bin_op_type,
+          source_pos: *Self::get_src_pos_bin_op(op),
+        }
+      }
+      None => bound_rhs,
+    };
+
+    let rhs_type = Self::get_type(&bound_rhs);
+    if !Self::types_match(&symbol.value_type, &rhs_type) {
+      expr_errors.push(Diagnostic::error(
+        DiagnosticCode::MismatchedTypes,
+        format!(
+          "Expected value of type {:?}, found {:?}.",
+          symbol.value_type, rhs_type
+        ),
+        Self::get_src_pos(rhs),
+      ));
+      return None;
+    }
+
+    Some(BoundStatement::BoundAssignment {
+      symbol,
+      rhs: bound_rhs,
+      source_pos: identifier.source_pos,
+    })
+  }
+
+  /// Returns whether the passed statements return a value on every path.
+  fn always_returns(stmts: &[BoundStatement]) -> bool {
+    stmts.iter().any(|statement| match statement {
+      BoundStatement::BoundReturn {
+        expr: _,
+        source_pos: _,
+      } => true,
+      BoundStatement::BoundIf {
+        condition: _,
+        then_body,
+        else_body: Some(else_body),
+      } => Self::always_returns(then_body) && Self::always_returns(else_body),
+      _ => false,
+    })
+  }
+
+  /// Binds a condition, making sure it evaluates to a `Type::Bool`.
+  fn bind_condition(
+    &mut self,
+    condition: &Expr,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> Result<BoundExpr, Diagnostic> {
+    let bound_condition = self.bind_expr(condition, expr_errors);
+
+    match Self::get_type(&bound_condition) {
+      Type::Bool | Type::Error => Ok(bound_condition),
+      condition_type => Err(Diagnostic::error(
+        DiagnosticCode::MismatchedTypes,
+        format!(
+          "Expected condition of type Bool, found {:?}.",
+          condition_type
+        ),
+        Self::get_src_pos(condition),
+      )),
+    }
+  }
+
+  /// Binds an Expression, pushing the errors of all of its sub-expressions to
+  /// `expr_errors`. The parts that fail to bind are replaced by
+  /// `BoundExpr::Error`.
+  pub fn bind_expr(&mut self, expr: &Expr, expr_errors: &mut Vec<Diagnostic>) -> BoundExpr {
+    let res = match expr {
+      Expr::Int { n, source_pos } => Self::bind_int(*n, source_pos),
+      Expr::Bool { b, source_pos } => Ok(BoundExpr::Bool {
+        b: b.to_owned(),
+        source_pos: *source_pos,
+      }),
+      Expr::ParenthesizedExpression { expr } => Ok(self.bind_expr(expr, expr_errors)),
+      Expr::UnaryOp { op, expr } => {
+        let source_pos = Self::get_src_pos_unary_op(op);
+        let expr = self.bind_expr(expr, expr_errors);
+        let expr_type = Self::get_type(&expr);
+
+        match (op, &expr_type) {
+          (UnaryOp::Neg { source_pos: _ }, Type::Int)
+          | (UnaryOp::Not { source_pos: _ }, Type::Bool)
+          | (_, Type::Error) => Ok(BoundExpr::BoundUnaryOp {
+            op: unary_to_bound_unary_op(*op),
+            expr: Box::new(expr),
+            source_pos: *source_pos,
+          }),
+          _ => Err(Diagnostic::error(
+            DiagnosticCode::InvalidOperand,
+            format!("Cannot perform '{}' on {:?}.", op, expr_type),
+            source_pos.to_owned(),
+          )),
+        }
+      }
+      Expr::BinaryOp { op, lhs, rhs } => {
+        let source_pos = Self::get_src_pos_bin_op(op);
+        let lhs_expr = self.bind_expr(lhs, expr_errors);
+        let rhs_expr = self.bind_expr(rhs, expr_errors);
+        let lhs_type = Self::get_type(&lhs_expr);
+        let rhs_type = Self::get_type(&rhs_expr);
+        let bin_op_type = match (&lhs_type, &rhs_type) {
+          (Type::Error, _) | (_, Type::Error) => Some(Type::Error),
+          _ if lhs_type == rhs_type => Self::get_op_type(op, &lhs_type),
+          _ => None,
+        };
+
+        match bin_op_type {
+          Some(bin_op_type) => Ok(BoundExpr::BoundBinaryOp {
+            op: binary_to_bound_binary_op(*op),
+            lhs: Box::new(lhs_expr),
+            rhs: Box::new(rhs_expr),
+            bin_op_type,
+            source_pos: *source_pos,
+          }),
+          None => Err(Self::invalid_op_err(&lhs_type, &rhs_type, op, source_pos)),
+        }
+      }
+      Expr::Print {
+        args,
+        newline,
+        source_pos,
+      } => Ok(self.bind_print(args, *newline, source_pos, expr_errors)),
       Expr::String {
         text: str,
-        source_pos: _,
+        source_pos,
       } => Ok(BoundExpr::String {
         str: str.to_owned(),
+        source_pos: *source_pos,
       }),
       Expr::Identifier(identifier) => self.retrieve_variable(identifier),
+      Expr::Call {
+        module,
+        identifier,
+        args,
+        source_pos,
+      } => Ok(self.bind_call(module, identifier, args, source_pos, expr_errors)),
+    };
+
+    res.unwrap_or_else(|err| {
+      expr_errors.push(err);
+      BoundExpr::Error {
+        source_pos: Self::get_src_pos(expr),
+      }
+    })
+  }
+
+  /// Binds a `print`/`println` call. A leading string literal is split into
+  /// the pieces around its placeholders, any other single argument is printed
+  /// on its own. The arguments are bound even if the format string is
+  /// invalid, their errors are pushed to `expr_errors`.
+  fn bind_print(
+    &mut self,
+    args: &[Expr],
+    newline: bool,
+    source_pos: &SourcePos,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> BoundExpr {
+    let (pieces, values) = match args.split_first() {
+      None => (Ok(vec![String::new()]), args),
+      Some((
+        Expr::String {
+          text,
+          source_pos: format_pos,
+        },
+        values,
+      )) => (Self::split_format_string(text, format_pos), values),
+      Some((_, [])) => (Ok(vec![String::new(), String::new()]), args),
+      Some((value, _)) => (
+        Err(Diagnostic::error(
+          DiagnosticCode::InvalidFormatString,
+          "Expected a format string as the first argument.",
+          Self::get_src_pos(value),
+        )),
+        args,
+      ),
+    };
+
+    let pieces = pieces.and_then(|pieces| match pieces.len() - 1 == values.len() {
+      true => Ok(pieces),
+      false => Err(Diagnostic::error(
+        DiagnosticCode::InvalidFormatString,
+        format!(
+          "Expected {} argument(s) for the format string, found {}.",
+          pieces.len() - 1,
+          values.len()
+        ),
+        source_pos.to_owned(),
+      )),
+    });
+    let pieces = match pieces {
+      Ok(pieces) => Some(pieces),
+      Err(err) => {
+        expr_errors.push(err);
+        None
+      }
+    };
+
+    let mut bound_args = Vec::new();
+    for value in values {
+      let bound_value = self.bind_expr(value, expr_errors);
+
+      if Self::get_type(&bound_value) == Type::Void {
+        expr_errors.push(Diagnostic::error(
+          DiagnosticCode::VoidValue,
+          "Cannot print a value of type Void.",
+          Self::get_src_pos(value),
+        ));
+      }
+
+      bound_args.push(bound_value);
+    }
+
+    // The format string error is already reported.
+    match pieces {
+      Some(pieces) => BoundExpr::BoundPrint {
+        pieces,
+        args: bound_args,
+        newline,
+        source_pos: *source_pos,
+      },
+      None => BoundExpr::Error {
+        source_pos: *source_pos,
+      },
     }
   }
 
-  /// Turns an `Identifier` into a `BoundExpr` or a `TypeError` if the variable
-  /// is not defined.
-  fn retrieve_variable(&self, identifier: &Identifier) -> Result<BoundExpr, TypeError> {
-    match self.bound_exprs.iter().find(|x| x.0 == identifier.text) {
-      Some(res) => {
-        let identifier = identifier.text.to_owned();
-        let value_type = Self::get_type(&res.1);
-        let rhs = Box::new(res.1.to_owned());
+  /// Splits a format string on its `{}` placeholders. `{{` and `}}` are used
+  /// to print literal braces.
+  fn split_format_string(text: &str, source_pos: &SourcePos) -> Result<Vec<String>, Diagnostic> {
+    let mut pieces = vec![String::new()];
+    let mut chars = text.chars().peekable();
 
-        Ok(BoundExpr::BoundDeclaration {
-          identifier,
-          value_type,
-          rhs,
-        })
+    while let Some(c) = chars.next() {
+      match (c, chars.peek()) {
+        ('{', Some('{')) | ('}', Some('}')) => {
+          chars.next();
+        }
+        ('{', Some('}')) => {
+          chars.next();
+          pieces.push(String::new());
+          continue;
+        }
+        ('{', _) | ('}', _) => {
+          return Err(Diagnostic::error(
+            DiagnosticCode::InvalidFormatString,
+            format!("Invalid format string, use '{}{}' to print '{}'.", c, c, c),
+            source_pos.to_owned(),
+          ))
+        }
+        _ => {}
+      }
+
+      pieces
+        .last_mut()
+        .expect("There is always at least one piece")
+        .push(c);
+    }
+
+    Ok(pieces)
+  }
+
+  /// Binds a function call, checking the number and types of the arguments
+  /// against the function's signature. The arguments are bound even if the
+  /// function cannot be called, their errors are pushed to `expr_errors`.
+  fn bind_call(
+    &mut self,
+    module: &Option<Identifier>,
+    identifier: &Identifier,
+    args: &[Expr],
+    source_pos: &SourcePos,
+    expr_errors: &mut Vec<Diagnostic>,
+  ) -> BoundExpr {
+    let signature = match module {
+      Some(module) => self.lookup_imported_function(module, identifier),
+      None => match self.functions.get(&identifier.text) {
+        Some(signature) => Ok(Some(signature.to_owned())),
+        None => Err(Diagnostic::error(
+          DiagnosticCode::UndefinedFunction,
+          "Function is undefined",
+          identifier.source_pos,
+        )),
+      },
+    };
+
+    let signature = signature.and_then(|signature| match signature {
+      Some(signature) if args.len() != signature.param_types.len() => Err(Diagnostic::error(
+        DiagnosticCode::ArgumentCount,
+        format!(
+          "Expected {} argument(s), found {}.",
+          signature.param_types.len(),
+          args.len()
+        ),
+        source_pos.to_owned(),
+      )),
+      signature => Ok(signature),
+    });
+    let signature = signature.unwrap_or_else(|err| {
+      expr_errors.push(err);
+      None
+    });
+
+    let mut bound_args = Vec::<BoundExpr>::new();
+    for (i, arg) in args.iter().enumerate() {
+      let bound_arg = self.bind_expr(arg, expr_errors);
+      let arg_type = Self::get_type(&bound_arg);
+
+      if let Some(param_type) = signature
+        .as_ref()
+        .map(|signature| &signature.param_types[i])
+      {
+        if !Self::types_match(param_type, &arg_type) {
+          expr_errors.push(Diagnostic::error(
+            DiagnosticCode::MismatchedTypes,
+            format!(
+              "Expected argument of type {:?}, found {:?}.",
+              param_type, arg_type
+            ),
+            Self::get_src_pos(arg),
+          ));
+        }
       }
-      None => Err(TypeError {
-        msg: "Variable is undefined".to_owned(),
+
+      bound_args.push(bound_arg);
+    }
+
+    // The reason the function cannot be called is already reported.
+    match signature {
+      Some(signature) => BoundExpr::BoundCall {
+        identifier: signature.name,
+        args: bound_args,
+        return_type: signature.return_type,
+        source_pos: *source_pos,
+      },
+      None => BoundExpr::Error {
+        source_pos: *source_pos,
+      },
+    }
+  }
+
+  /// Looks up a public function of a module imported by the module being bound.
+  /// Returns `None` if importing the module forms a cycle, which is already
+  /// reported by `Self::bind_program`.
+  fn lookup_imported_function(
+    &self,
+    module: &Identifier,
+    identifier: &Identifier,
+  ) -> Result<Option<FunctionSignature>, Diagnostic> {
+    let functions = match self.modules.get(&module.text) {
+      Some(functions) if self.imports.contains(&module.text) => functions,
+      // Modules whose import forms a cycle are imported but never bound.
+      None if self.imports.contains(&module.text) => return Ok(None),
+      _ => {
+        return Err(
+          Diagnostic::error(
+            DiagnosticCode::UnknownModule,
+            format!("Module '{}' is not imported.", module.text),
+            module.source_pos,
+          )
+          .with_help(format!(
+            "add 'import {};' to the top of the file",
+            module.text
+          )),
+        )
+      }
+    };
+
+    match functions.get(&identifier.text) {
+      Some(signature) if signature.public => Ok(Some(signature.to_owned())),
+      Some(signature) => Err(
+        Diagnostic::error(
+          DiagnosticCode::PrivateFunction,
+          format!(
+            "Function '{}' is private to module '{}'.",
+            identifier.text, module.text
+          ),
+          identifier.source_pos,
+        )
+        .with_label(signature.source_pos, "declared here")
+        .with_help(format!("make it public: 'pub fn {}'", identifier.text)),
+      ),
+      None => Err(Diagnostic::error(
+        DiagnosticCode::UndefinedFunction,
+        format!(
+          "Function '{}' is not defined in module '{}'.",
+          identifier.text, module.text
+        ),
+        identifier.source_pos,
+      )),
+    }
+  }
+
+  /// Resolves an `Identifier` to the symbol it refers to or returns a `Diagnostic`
+  /// if the variable is not defined.
+  fn retrieve_variable(&self, identifier: &Identifier) -> Result<BoundExpr, Diagnostic> {
+    match self.symbol_table.lookup(&identifier.text) {
+      Some(symbol) => Ok(BoundExpr::BoundVariable {
+        symbol: symbol.to_owned(),
         source_pos: identifier.source_pos,
       }),
+      None => Err(Diagnostic::error(
+        DiagnosticCode::UndefinedVariable,
+        "Variable is undefined",
+        identifier.source_pos,
+      )),
     }
   }
 
   /// Returns the `Type` of the passed bound expression.
-  fn get_type(expr: &BoundExpr) -> Type {
+  pub(crate) fn get_type(expr: &BoundExpr) -> Type {
     match expr {
-      BoundExpr::Int { n: _ } => Type::Int,
-      BoundExpr::Bool { b: _ } => Type::Bool,
+      BoundExpr::Int {
+        n: _,
+        source_pos: _,
+      } => Type::Int,
+      BoundExpr::Bool {
+        b: _,
+        source_pos: _,
+      } => Type::Bool,
       BoundExpr::BoundBinaryOp {
         op: _,
         lhs: _,
         rhs: _,
         bin_op_type,
+        source_pos: _,
       } => bin_op_type.to_owned(),
+      BoundExpr::BoundUnaryOp {
+        op: _,
+        expr,
+        source_pos: _,
+      } => Self::get_type(expr),
       BoundExpr::ParenthesizedExpression { expr } => Self::get_type(expr),
-      BoundExpr::BoundPrint { expr_type, expr: _ } => expr_type.to_owned(),
-      BoundExpr::String { str: _ } => Type::String,
-      BoundExpr::BoundDeclaration {
+      BoundExpr::BoundPrint {
+        pieces: _,
+        args: _,
+        newline: _,
+        source_pos: _,
+      } => Type::Void,
+      BoundExpr::String {
+        str: _,
+        source_pos: _,
+      } => Type::String,
+      BoundExpr::BoundDeclaration { symbol, rhs: _ } => symbol.value_type.to_owned(),
+      BoundExpr::BoundVariable {
+        symbol,
+        source_pos: _,
+      } => symbol.value_type.to_owned(),
+      BoundExpr::BoundCall {
         identifier: _,
-        value_type,
-        rhs: _,
-      } => value_type.to_owned(),
+        args: _,
+        return_type,
+        source_pos: _,
+      } => return_type.to_owned(),
+      BoundExpr::Error { source_pos: _ } => Type::Error,
     }
   }
 
+  /// Returns whether a value of type `found` can be used where `expected` is
+  /// required. The error type matches everything so that it is only reported
+  /// once.
+  fn types_match(expected: &Type, found: &Type) -> bool {
+    expected == found || *expected == Type::Error || *found == Type::Error
+  }
+
   /// Returns the source position of the passed expression.
   fn get_src_pos(expr: &Expr) -> SourcePos {
     match expr {
@@ -231,12 +1071,28 @@ impl Binder {
         let rhs_pos = Self::get_src_pos(rhs);
 
         SourcePos {
+          file: lhs_pos.file,
           start: lhs_pos.start,
           end: rhs_pos.end,
         }
       }
+      Expr::UnaryOp { op, expr } => SourcePos {
+        file: Self::get_src_pos_unary_op(op).file,
+        start: Self::get_src_pos_unary_op(op).start,
+        end: Self::get_src_pos(expr).end,
+      },
       Expr::ParenthesizedExpression { expr } => Self::get_src_pos(expr),
-      Expr::Print { expr } => Self::get_src_pos(expr),
+      Expr::Print {
+        args: _,
+        newline: _,
+        source_pos,
+      } => *source_pos,
+      Expr::Call {
+        module: _,
+        identifier: _,
+        args: _,
+        source_pos,
+      } => *source_pos,
     }
   }
 
@@ -249,27 +1105,69 @@ impl Binder {
       BinaryOp::Div { source_pos } => source_pos,
       BinaryOp::And { source_pos } => source_pos,
       BinaryOp::Or { source_pos } => source_pos,
+      BinaryOp::Eq { source_pos } => source_pos,
+      BinaryOp::Ne { source_pos } => source_pos,
+      BinaryOp::Lt { source_pos } => source_pos,
+      BinaryOp::Le { source_pos } => source_pos,
+      BinaryOp::Gt { source_pos } => source_pos,
+      BinaryOp::Ge { source_pos } => source_pos,
     }
   }
 
-  /// Returns the operator's `(InputType, OutputType)`
-  fn get_op_type(expr: &BinaryOp) -> (Type, Type) {
-    match expr {
-      BinaryOp::Add { source_pos: _ }
-      | BinaryOp::Sub { source_pos: _ }
-      | BinaryOp::Mul { source_pos: _ }
-      | BinaryOp::Div { source_pos: _ } => (Type::Int, Type::Int),
-      BinaryOp::And { source_pos: _ } | BinaryOp::Or { source_pos: _ } => (Type::Bool, Type::Bool),
+  /// `Self::get_src_pos` variant specific to unary ops.
+  fn get_src_pos_unary_op(op: &'_ UnaryOp) -> &'_ SourcePos {
+    match op {
+      UnaryOp::Neg { source_pos } => source_pos,
+      UnaryOp::Not { source_pos } => source_pos,
     }
   }
 
-  /// Helper method for throwing an invalid operator error.
-  fn invalid_op_err(
-    lhs: &Type,
-    rhs: &Type,
-    op: &BinaryOp,
-    source_pos: &SourcePos,
-  ) -> Result<BoundExpr, TypeError> {
+  /// Binds an integer literal, making sure that it fits in an `i32`.
+  fn bind_int(n: i64, source_pos: &SourcePos) -> Result<BoundExpr, Diagnostic> {
+    match i32::try_from(n) {
+      Ok(n) => Ok(BoundExpr::Int {
+        n,
+        source_pos: *source_pos,
+      }),
+      Err(_) => Err(Diagnostic::error(
+        DiagnosticCode::IntegerOutOfRange,
+        "Integer literal is out of range for Int.",
+        source_pos.to_owned(),
+      )),
+    }
+  }
+
+  /// Returns the operator's output type when applied to two operands of
+  /// `operand_type` or `None` if the operator does not support that type.
+  fn get_op_type(expr: &BinaryOp, operand_type: &Type) -> Option<Type> {
+    match (expr, operand_type) {
+      (
+        BinaryOp::Add { source_pos: _ }
+        | BinaryOp::Sub { source_pos: _ }
+        | BinaryOp::Mul { source_pos: _ }
+        | BinaryOp::Div { source_pos: _ },
+        Type::Int,
+      ) => Some(Type::Int),
+      (BinaryOp::And { source_pos: _ } | BinaryOp::Or { source_pos: _ }, Type::Bool) => {
+        Some(Type::Bool)
+      }
+      (
+        BinaryOp::Eq { source_pos: _ } | BinaryOp::Ne { source_pos: _ },
+        Type::Int | Type::Bool | Type::String,
+      ) => Some(Type::Bool),
+      (
+        BinaryOp::Lt { source_pos: _ }
+        | BinaryOp::Le { source_pos: _ }
+        | BinaryOp::Gt { source_pos: _ }
+        | BinaryOp::Ge { source_pos: _ },
+        Type::Int,
+      ) => Some(Type::Bool),
+      _ => None,
+    }
+  }
+
+  /// Helper method for creating an invalid operator error.
+  fn invalid_op_err(lhs: &Type, rhs: &Type, op: &BinaryOp, source_pos: &SourcePos) -> Diagnostic {
     let op_type = op.to_string();
     let msg = format!(
       "Cannot perform '{}' between {:?} and {:?}.",
@@ -277,10 +1175,210 @@ impl Binder {
       lhs,
       rhs
     );
-    let err = TypeError {
-      msg,
-      source_pos: source_pos.to_owned(),
-    };
-    Err(err)
+    Diagnostic::error(DiagnosticCode::InvalidOperand, msg, source_pos.to_owned())
+  }
+
+  /// Helper method for creating a `break`/`continue` outside of a loop error.
+  fn outside_loop_err(keyword: &str, source_pos: &SourcePos) -> Diagnostic {
+    Diagnostic::error(
+      DiagnosticCode::OutsideLoop,
+      format!("'{}' can only be used inside of a loop.", keyword),
+      source_pos.to_owned(),
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    ast::Module,
+    bind_sources,
+    diagnostic::{Diagnostic, DiagnosticCode},
+    parse,
+    parser::ProgramParser,
+    source_map::SourceMap,
+  };
+
+  use super::Binder;
+
+  /// Binds the source, returning the codes of the errors in the order they
+  /// are reported.
+  fn error_codes(source: &str) -> Vec<DiagnosticCode> {
+    module_error_codes(&[("main", source)])
+  }
+
+  fn module_error_codes(sources: &[(&str, &str)]) -> Vec<DiagnosticCode> {
+    module_errors(sources).iter().map(|err| err.code).collect()
+  }
+
+  /// Binds the `(name, source)` modules, returning the errors reported.
+  fn module_errors(sources: &[(&str, &str)]) -> Vec<Diagnostic> {
+    match bind_sources(sources) {
+      Ok(_) => Vec::new(),
+      Err(err) => err.expr_errors,
+    }
+  }
+
+  #[test]
+  fn every_operand_and_argument_is_reported() {
+    let codes = error_codes(
+      "fn f(a: int, b: int) -> int { return a + b; }
+      let x = f(undefa, undefb);
+      let y = undefc + undefd;",
+    );
+
+    assert_eq!(codes, vec![DiagnosticCode::UndefinedVariable; 4]);
+  }
+
+  #[test]
+  fn arguments_of_invalid_calls_are_reported() {
+    let codes = error_codes("let x = g(undefa, 1 + true); f(1, undefb);");
+
+    assert_eq!(
+      codes,
+      vec![
+        DiagnosticCode::UndefinedFunction,
+        DiagnosticCode::UndefinedVariable,
+        DiagnosticCode::InvalidOperand,
+        DiagnosticCode::UndefinedFunction,
+        DiagnosticCode::UndefinedVariable,
+      ]
+    );
+  }
+
+  #[test]
+  fn arguments_of_invalid_prints_are_reported() {
+    let codes = error_codes("println(\"{} {}\", undefa); print(1, undefb);");
+
+    assert_eq!(
+      codes,
+      vec![
+        DiagnosticCode::InvalidFormatString,
+        DiagnosticCode::UndefinedVariable,
+        DiagnosticCode::InvalidFormatString,
+        DiagnosticCode::UndefinedVariable,
+      ]
+    );
+  }
+
+  #[test]
+  fn mismatched_annotations_point_at_the_annotation() {
+    let errors = module_errors(&[("main", "let x: int = true;")]);
+    let spans = errors
+      .iter()
+      .flat_map(|err| std::iter::once(&err.primary_label).chain(&err.secondary_labels))
+      .map(|label| {
+        (
+          label.source_pos.start,
+          label.source_pos.end,
+          label.msg.as_str(),
+        )
+      })
+      .collect::<Vec<_>>();
+
+    assert_eq!(
+      errors.iter().map(|err| err.code).collect::<Vec<_>>(),
+      vec![DiagnosticCode::MismatchedTypes]
+    );
+    assert_eq!(
+      spans,
+      vec![
+        (13, 17, "expected Int, found Bool"),
+        (7, 10, "expected due to this"),
+      ]
+    );
+  }
+
+  #[test]
+  fn annotated_variables_keep_their_type_if_the_value_is_invalid() {
+    let codes = error_codes("let x: int = undefa; let y = x + 1; let z: bool = x;");
+
+    assert_eq!(
+      codes,
+      vec![
+        DiagnosticCode::UndefinedVariable,
+        DiagnosticCode::MismatchedTypes
+      ]
+    );
+  }
+
+  #[test]
+  fn annotated_variables_cannot_be_void() {
+    let codes = error_codes("let x: int = print(1); let y = x + 1;");
+
+    assert_eq!(codes, vec![DiagnosticCode::VoidValue]);
+  }
+
+  #[test]
+  fn calls_to_reserved_functions_are_not_reported_again() {
+    let codes = error_codes("fn printf(a: int) {} printf(1); let x = printf(2) + 1;");
+
+    assert_eq!(codes, vec![DiagnosticCode::ReservedFunctionName]);
+  }
+
+  #[test]
+  fn imported_functions_can_use_reserved_names() {
+    let codes = module_error_codes(&[
+      ("main", "import util; util::main(); util::printf();"),
+      ("util", "pub fn main() {} pub fn printf() {}"),
+    ]);
+
+    assert!(codes.is_empty());
+  }
+
+  #[test]
+  fn errors_are_not_reported_twice() {
+    let codes = error_codes("let x = undefa; let y = -x + 1; if x { print(\"{}\", x); }");
+
+    assert_eq!(codes, vec![DiagnosticCode::UndefinedVariable]);
+  }
+
+  #[test]
+  fn calls_into_cyclic_imports_are_not_reported_again() {
+    let codes = module_error_codes(&[
+      ("main", "import math; let x = math::f();"),
+      (
+        "math",
+        "import main; pub fn f() -> int { return main::g(undefa); }",
+      ),
+    ]);
+
+    assert_eq!(
+      codes,
+      vec![
+        DiagnosticCode::ImportCycle,
+        DiagnosticCode::UndefinedVariable
+      ]
+    );
+  }
+
+  #[test]
+  fn lexer_errors_are_recovered_from() {
+    let source = "let a = 1 $ 2; print(\"it's\"); let b: int = true;";
+    let parser = ProgramParser::new();
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main", source);
+
+    let (program, syntax_errors) = parse(&parser, file, source);
+    let mut codes = syntax_errors
+      .iter()
+      .map(|err| Diagnostic::from_parse_error(&parser, source_map.get(file), err).code)
+      .collect::<Vec<DiagnosticCode>>();
+    let modules = [Module {
+      name: "main".to_owned(),
+      program: program.expect("Parser should recover"),
+    }];
+    if let Err(err) = Binder::new().bind_modules(&modules) {
+      codes.extend(err.expr_errors.iter().map(|err| err.code));
+    }
+
+    assert_eq!(
+      codes,
+      vec![
+        DiagnosticCode::InvalidToken,
+        DiagnosticCode::InvalidToken,
+        DiagnosticCode::MismatchedTypes,
+      ]
+    );
   }
 }
@@ -1,14 +1,11 @@
-use crate::ast::{BinaryOp, SourcePos};
+use crate::{
+  ast::{BinaryOp, SourcePos, TypeName, UnaryOp},
+  diagnostic::Diagnostic,
+};
 
 #[derive(Debug)]
 pub struct ProgramError {
-  pub expr_errors: Vec<TypeError>,
-}
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct TypeError {
-  pub msg: String,
-  pub source_pos: SourcePos,
+  pub expr_errors: Vec<Diagnostic>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -18,7 +15,88 @@ pub enum BoundProgram {
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum BoundStatement {
-  BoundExpr { expr: BoundExpr },
+  BoundExpr {
+    expr: BoundExpr,
+  },
+  BoundIf {
+    condition: BoundExpr,
+    then_body: Vec<BoundStatement>,
+    else_body: Option<Vec<BoundStatement>>,
+  },
+  BoundWhile {
+    condition: BoundExpr,
+    body: Vec<BoundStatement>,
+  },
+  BoundBreak {
+    source_pos: SourcePos,
+  },
+  BoundContinue {
+    source_pos: SourcePos,
+  },
+  BoundFunction {
+    identifier: String,
+    params: Vec<Symbol>,
+    return_type: Type,
+    body: Vec<BoundStatement>,
+    /// Position of the function's name.
+    source_pos: SourcePos,
+  },
+  BoundReturn {
+    expr: Option<BoundExpr>,
+    /// Position of the `return` keyword.
+    source_pos: SourcePos,
+  },
+  BoundAssignment {
+    symbol: Symbol,
+    rhs: BoundExpr,
+    /// Position of the assigned variable.
+    source_pos: SourcePos,
+  },
+}
+
+impl BoundStatement {
+  /// Returns the position the statement is reported at.
+  pub fn source_pos(&self) -> SourcePos {
+    match self {
+      BoundStatement::BoundExpr { expr } => expr.source_pos(),
+      BoundStatement::BoundIf {
+        condition,
+        then_body: _,
+        else_body: _,
+      } => condition.source_pos(),
+      BoundStatement::BoundWhile { condition, body: _ } => condition.source_pos(),
+      BoundStatement::BoundBreak { source_pos } => *source_pos,
+      BoundStatement::BoundContinue { source_pos } => *source_pos,
+      BoundStatement::BoundFunction {
+        identifier: _,
+        params: _,
+        return_type: _,
+        body: _,
+        source_pos,
+      } => *source_pos,
+      BoundStatement::BoundReturn {
+        expr: _,
+        source_pos,
+      } => *source_pos,
+      BoundStatement::BoundAssignment {
+        symbol: _,
+        rhs: _,
+        source_pos,
+      } => *source_pos,
+    }
+  }
+}
+
+/// A declared variable or parameter.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Symbol {
+  pub name: String,
+  pub value_type: Type,
+  pub mutable: bool,
+  /// Where the symbol was declared.
+  pub source_pos: SourcePos,
+  /// Unique for every declaration, shadowed variables share their name but not their id.
+  pub id: usize,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -26,39 +104,140 @@ pub enum Type {
   Int,
   Bool,
   String,
+  /// The type of functions without a return value.
+  Void,
+  /// The type of expressions that failed to bind. Errors involving it are not
+  /// reported since the original error already was.
+  Error,
+}
+
+impl From<TypeName> for Type {
+  fn from(type_name: TypeName) -> Self {
+    match type_name {
+      TypeName::Int => Type::Int,
+      TypeName::Bool => Type::Bool,
+      TypeName::String => Type::String,
+    }
+  }
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum BoundExpr {
   BoundDeclaration {
-    identifier: String,
-    value_type: Type,
+    symbol: Symbol,
     rhs: Box<BoundExpr>,
   },
+  BoundVariable {
+    symbol: Symbol,
+    /// Where the variable is used, the symbol holds where it was declared.
+    source_pos: SourcePos,
+  },
   Int {
     n: i32,
+    source_pos: SourcePos,
   },
   Bool {
     b: bool,
+    source_pos: SourcePos,
   },
   String {
     str: String,
+    source_pos: SourcePos,
   },
   BoundBinaryOp {
     op: BoundBinaryOp,
     lhs: Box<BoundExpr>,
     rhs: Box<BoundExpr>,
     bin_op_type: Type,
+    /// Position of the operator, used for reporting runtime errors.
+    source_pos: SourcePos,
+  },
+  /// Unary operators evaluate to the type of their operand.
+  BoundUnaryOp {
+    op: BoundUnaryOp,
+    expr: Box<BoundExpr>,
+    /// Position of the operator.
+    source_pos: SourcePos,
   },
   ParenthesizedExpression {
     expr: Box<BoundExpr>,
   },
+  /// `pieces` holds the text around the placeholders of the format string,
+  /// there is always one more piece than there are arguments.
   BoundPrint {
-    expr_type: Type,
-    expr: Box<BoundExpr>,
+    pieces: Vec<String>,
+    args: Vec<BoundExpr>,
+    newline: bool,
+    source_pos: SourcePos,
+  },
+  BoundCall {
+    identifier: String,
+    args: Vec<BoundExpr>,
+    return_type: Type,
+    source_pos: SourcePos,
+  },
+  /// Stands in for an expression that failed to bind so that the rest of the
+  /// program can still be checked, it is of the error type. Programs containing
+  /// it are never run.
+  Error {
+    source_pos: SourcePos,
   },
 }
 
+impl BoundExpr {
+  /// Returns the span of the whole expression.
+  pub fn source_pos(&self) -> SourcePos {
+    match self {
+      BoundExpr::BoundDeclaration { symbol, rhs } => SourcePos {
+        file: symbol.source_pos.file,
+        start: symbol.source_pos.start,
+        end: rhs.source_pos().end,
+      },
+      BoundExpr::BoundVariable {
+        symbol: _,
+        source_pos,
+      } => *source_pos,
+      BoundExpr::Int { n: _, source_pos } => *source_pos,
+      BoundExpr::Bool { b: _, source_pos } => *source_pos,
+      BoundExpr::String { str: _, source_pos } => *source_pos,
+      BoundExpr::BoundBinaryOp {
+        op: _,
+        lhs,
+        rhs,
+        bin_op_type: _,
+        source_pos: _,
+      } => SourcePos {
+        file: lhs.source_pos().file,
+        start: lhs.source_pos().start,
+        end: rhs.source_pos().end,
+      },
+      BoundExpr::BoundUnaryOp {
+        op: _,
+        expr,
+        source_pos,
+      } => SourcePos {
+        file: source_pos.file,
+        start: source_pos.start,
+        end: expr.source_pos().end,
+      },
+      BoundExpr::ParenthesizedExpression { expr } => expr.source_pos(),
+      BoundExpr::BoundPrint {
+        pieces: _,
+        args: _,
+        newline: _,
+        source_pos,
+      } => *source_pos,
+      BoundExpr::BoundCall {
+        identifier: _,
+        args: _,
+        return_type: _,
+        source_pos,
+      } => *source_pos,
+      BoundExpr::Error { source_pos } => *source_pos,
+    }
+  }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BoundBinaryOp {
   Add,
@@ -67,6 +246,12 @@ pub enum BoundBinaryOp {
   Div,
   And,
   Or,
+  Eq,
+  Ne,
+  Lt,
+  Le,
+  Gt,
+  Ge,
 }
 
 pub fn binary_to_bound_binary_op(op: BinaryOp) -> BoundBinaryOp {
@@ -75,7 +260,26 @@ pub fn binary_to_bound_binary_op(op: BinaryOp) -> BoundBinaryOp {
     BinaryOp::Sub { source_pos: _ } => BoundBinaryOp::Sub,
     BinaryOp::Mul { source_pos: _ } => BoundBinaryOp::Mul,
     BinaryOp::Div { source_pos: _ } => BoundBinaryOp::Div,
-    BinaryOp::And { source_pos: _ } => BoundBinaryOp::Add,
+    BinaryOp::And { source_pos: _ } => BoundBinaryOp::And,
     BinaryOp::Or { source_pos: _ } => BoundBinaryOp::Or,
+    BinaryOp::Eq { source_pos: _ } => BoundBinaryOp::Eq,
+    BinaryOp::Ne { source_pos: _ } => BoundBinaryOp::Ne,
+    BinaryOp::Lt { source_pos: _ } => BoundBinaryOp::Lt,
+    BinaryOp::Le { source_pos: _ } => BoundBinaryOp::Le,
+    BinaryOp::Gt { source_pos: _ } => BoundBinaryOp::Gt,
+    BinaryOp::Ge { source_pos: _ } => BoundBinaryOp::Ge,
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundUnaryOp {
+  Neg,
+  Not,
+}
+
+pub fn unary_to_bound_unary_op(op: UnaryOp) -> BoundUnaryOp {
+  match op {
+    UnaryOp::Neg { source_pos: _ } => BoundUnaryOp::Neg,
+    UnaryOp::Not { source_pos: _ } => BoundUnaryOp::Not,
   }
 }
@@ -1,3 +1,4 @@
 #![allow(dead_code)]
 pub mod binder;
 pub mod bound_ast;
+mod symbol_table;
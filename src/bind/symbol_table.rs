@@ -0,0 +1,87 @@
+use std::collections::HashMap;
+
+use crate::ast::SourcePos;
+
+use super::bound_ast::{Symbol, Type};
+
+type Scope = HashMap<String, Symbol>;
+
+/// A stack of lexical scopes, the first of which is the global scope.
+#[derive(Clone)]
+pub struct SymbolTable {
+  scopes: Vec<Scope>,
+  /// Used for giving every declared symbol a unique id.
+  symbol_count: usize,
+}
+
+impl Default for SymbolTable {
+  fn default() -> Self {
+    Self {
+      scopes: vec![Scope::new()],
+      symbol_count: 0,
+    }
+  }
+}
+
+impl SymbolTable {
+  pub fn push_scope(&mut self) {
+    self.scopes.push(Scope::new());
+  }
+
+  pub fn pop_scope(&mut self) {
+    self.scopes.pop();
+  }
+
+  /// Returns whether symbols are currently declared in the global scope.
+  pub fn is_global_scope(&self) -> bool {
+    self.scopes.len() == 1
+  }
+
+  /// Hides every scope declared so far behind a single, empty one and returns
+  /// the hidden scopes so that they can be passed to `Self::restore` later.
+  pub fn isolate(&mut self) -> Vec<Scope> {
+    std::mem::replace(&mut self.scopes, vec![Scope::new()])
+  }
+
+  /// Restores scopes previously hidden by `Self::isolate`.
+  pub fn restore(&mut self, scopes: Vec<Scope>) {
+    self.scopes = scopes;
+  }
+
+  /// Declares a symbol in the innermost scope. If the scope already contains
+  /// a symbol with the same name, that symbol is returned as the error.
+  pub fn declare(
+    &mut self,
+    name: &str,
+    value_type: Type,
+    mutable: bool,
+    source_pos: SourcePos,
+  ) -> Result<Symbol, Symbol> {
+    let symbol = Symbol {
+      name: name.to_owned(),
+      value_type,
+      mutable,
+      source_pos,
+      id: self.symbol_count,
+    };
+
+    let scope = self
+      .scopes
+      .last_mut()
+      .expect("The global scope is never popped");
+
+    if let Some(existing) = scope.get(name) {
+      return Err(existing.to_owned());
+    }
+
+    self.symbol_count += 1;
+    scope.insert(name.to_owned(), symbol.to_owned());
+
+    Ok(symbol)
+  }
+
+  /// Looks a symbol up, starting from the innermost scope.
+  pub fn lookup(&self, name: &str) -> Option<&Symbol> {
+    self.scopes.iter().rev().find_map(|scope| scope.get(name))
+  }
+}
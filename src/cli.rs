@@ -0,0 +1,145 @@
+use rust_sharp::{code_gen::driver::Toolchain, diagnostic::render::ColorChoice};
+
+pub const USAGE: &str = "Usage: rust_sharp [--color=<when>] <command> [options]
+
+Commands:
+  check <file>                          Parse and type check a program
+  build <file> [options]                Compile a program to an executable
+  run <file>                            Run a program using the interpreter
+  emit <tokens|ast|bound|llvm> <file>   Print an intermediate representation
+  repl                                  Start an interactive session
+  help                                  Print this message
+
+Options:
+  --color=<never|always|auto>           When to color errors, defaults to auto
+
+Build options:
+  -o <output>                           Path of the executable
+  -O<level>                             Optimization level passed to llc, 0 to 3
+  --llc <path>                          The llc executable to use
+  --cc <path>                           The C compiler used for linking";
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct Args {
+  pub command: Command,
+  pub color: ColorChoice,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Command {
+  Check {
+    file: String,
+  },
+  Build {
+    file: String,
+    output: Option<String>,
+    toolchain: Toolchain,
+  },
+  Run {
+    file: String,
+  },
+  Emit {
+    kind: EmitKind,
+    file: String,
+  },
+  Repl,
+  Help,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EmitKind {
+  Tokens,
+  Ast,
+  Bound,
+  Llvm,
+}
+
+/// Parses the command line arguments, excluding the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+  // `--color` is accepted anywhere, the remaining arguments are positional.
+  let mut color = ColorChoice::Auto;
+  let mut remaining = Vec::new();
+  for arg in args {
+    match arg.strip_prefix("--color=") {
+      Some("never") => color = ColorChoice::Never,
+      Some("always") => color = ColorChoice::Always,
+      Some("auto") => color = ColorChoice::Auto,
+      Some(when) => return Err(format!("Unknown color choice '{}'.", when)),
+      None => remaining.push(arg),
+    }
+  }
+  let mut args = remaining.into_iter();
+
+  let command = match args.next() {
+    Some(command) => command,
+    None => return Err("No command given.".to_owned()),
+  };
+
+  let command = match command.as_str() {
+    "check" => Command::Check {
+      file: expect_file(&mut args)?,
+    },
+    "build" => {
+      let file = expect_file(&mut args)?;
+      let mut output = None;
+      let mut toolchain = Toolchain::default();
+
+      while let Some(arg) = args.next() {
+        match arg.as_str() {
+          "-o" => output = Some(expect_value(&mut args, &arg)?),
+          "--llc" => toolchain.llc = expect_value(&mut args, &arg)?,
+          "--cc" => toolchain.linker = Some(expect_value(&mut args, &arg)?),
+          "-O0" => toolchain.opt_level = 0,
+          "-O1" => toolchain.opt_level = 1,
+          "-O2" => toolchain.opt_level = 2,
+          "-O3" => toolchain.opt_level = 3,
+          _ => return Err(format!("Unexpected argument '{}'.", arg)),
+        }
+      }
+
+      Command::Build {
+        file,
+        output,
+        toolchain,
+      }
+    }
+    "run" => Command::Run {
+      file: expect_file(&mut args)?,
+    },
+    "emit" => {
+      let kind = match args.next().as_deref() {
+        Some("tokens") => EmitKind::Tokens,
+        Some("ast") => EmitKind::Ast,
+        Some("bound") => EmitKind::Bound,
+        Some("llvm") => EmitKind::Llvm,
+        Some(kind) => return Err(format!("Unknown emit kind '{}'.", kind)),
+        None => return Err("Expected one of 'tokens', 'ast', 'bound' or 'llvm'.".to_owned()),
+      };
+
+      Command::Emit {
+        kind,
+        file: expect_file(&mut args)?,
+      }
+    }
+    "repl" => Command::Repl,
+    "help" | "-h" | "--help" => Command::Help,
+    _ => return Err(format!("Unknown command '{}'.", command)),
+  };
+
+  match args.next() {
+    Some(arg) => Err(format!("Unexpected argument '{}'.", arg)),
+    None => Ok(Args { command, color }),
+  }
+}
+
+fn expect_value(args: &mut impl Iterator<Item = String>, option: &str) -> Result<String, String> {
+  args
+    .next()
+    .ok_or_else(|| format!("Expected a value after '{}'.", option))
+}
+
+fn expect_file(args: &mut impl Iterator<Item = String>) -> Result<String, String> {
+  args
+    .next()
+    .ok_or_else(|| "Expected a file path.".to_owned())
+}
@@ -0,0 +1,173 @@
+// turns LLVM modules into native executables using the system's toolchain
+
+use std::{
+  fmt, fs,
+  io::ErrorKind,
+  path::{Path, PathBuf},
+  process::Command,
+  time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The external tools used for building executables.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Toolchain {
+  pub llc: String,
+  /// The C compiler used for linking. If `None`, `clang` is tried first and
+  /// `cc` second.
+  pub linker: Option<String>,
+  /// Passed to `llc` as `-O<level>`, between 0 and 3.
+  pub opt_level: u8,
+}
+
+impl Default for Toolchain {
+  fn default() -> Self {
+    Self {
+      llc: "llc".to_owned(),
+      linker: None,
+      opt_level: 0,
+    }
+  }
+}
+
+#[derive(Debug)]
+pub enum DriverError {
+  /// The tool could not be found, i.e. it is not installed or not on `PATH`.
+  ToolNotFound {
+    tool: String,
+  },
+  /// The tool ran but did not succeed.
+  ToolFailed {
+    tool: String,
+    stderr: String,
+  },
+  Io {
+    path: PathBuf,
+    err: std::io::Error,
+  },
+}
+
+impl fmt::Display for DriverError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      DriverError::ToolNotFound { tool } => write!(
+        f,
+        "Could not find '{}', make sure it is installed and on your PATH.",
+        tool
+      ),
+      DriverError::ToolFailed { tool, stderr } => write!(f, "'{}' failed:\n{}", tool, stderr),
+      DriverError::Io { path, err } => write!(f, "Unable to write {}: {}", path.display(), err),
+    }
+  }
+}
+
+/// Compiles the LLVM module into an executable at `output`. The intermediate
+/// `.ll` and object files are placed in a temporary directory of their own,
+/// which is removed afterwards.
+pub fn build_executable(
+  module: &str,
+  output: &Path,
+  toolchain: &Toolchain,
+) -> Result<(), DriverError> {
+  let dir = create_temp_dir()?;
+  let ll_path = dir.join("module.ll");
+  let obj_path = dir.join("module.o");
+
+  let result = fs::write(&ll_path, module)
+    .map_err(|err| DriverError::Io {
+      path: ll_path.to_owned(),
+      err,
+    })
+    .and_then(|_| compile_and_link(&ll_path, &obj_path, output, toolchain));
+
+  fs::remove_dir_all(&dir).ok();
+
+  result
+}
+
+/// Creates a new, empty directory under the system's temporary directory.
+fn create_temp_dir() -> Result<PathBuf, DriverError> {
+  let nanos = SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map_or(0, |duration| duration.subsec_nanos());
+
+  // Another build may have picked the same name, in which case the next one
+  // is tried.
+  let mut attempt = 0;
+  loop {
+    let dir = std::env::temp_dir().join(format!(
+      "rust_sharp-{}-{}-{}",
+      std::process::id(),
+      nanos,
+      attempt
+    ));
+
+    match fs::create_dir(&dir) {
+      Ok(_) => return Ok(dir),
+      Err(err) if err.kind() == ErrorKind::AlreadyExists && attempt < 100 => attempt += 1,
+      Err(err) => return Err(DriverError::Io { path: dir, err }),
+    }
+  }
+}
+
+fn compile_and_link(
+  ll_path: &Path,
+  obj_path: &Path,
+  output: &Path,
+  toolchain: &Toolchain,
+) -> Result<(), DriverError> {
+  // Position independent code can be linked into the PIE executables most
+  // linkers default to.
+  let mut llc = Command::new(&toolchain.llc);
+  llc
+    .arg(format!("-O{}", toolchain.opt_level))
+    .arg("-filetype=obj")
+    .arg("-relocation-model=pic")
+    .arg(ll_path)
+    .arg("-o")
+    .arg(obj_path);
+  run_tool(&toolchain.llc, &mut llc)?;
+
+  let linkers = match &toolchain.linker {
+    Some(linker) => vec![linker.as_str()],
+    None => vec!["clang", "cc"],
+  };
+
+  for (i, linker) in linkers.iter().enumerate() {
+    let mut link = Command::new(linker);
+    link.arg(obj_path).arg("-o").arg(output);
+
+    match run_tool(linker, &mut link) {
+      Err(DriverError::ToolNotFound { tool: _ }) if i + 1 < linkers.len() => continue,
+      result => return result,
+    }
+  }
+
+  Ok(())
+}
+
+/// Runs the command to completion, turning a missing executable or a non-zero
+/// exit status into an error.
+fn run_tool(tool: &str, command: &mut Command) -> Result<(), DriverError> {
+  let output = match command.output() {
+    Ok(output) => output,
+    Err(err) if err.kind() == ErrorKind::NotFound => {
+      return Err(DriverError::ToolNotFound {
+        tool: tool.to_owned(),
+      })
+    }
+    Err(err) => {
+      return Err(DriverError::ToolFailed {
+        tool: tool.to_owned(),
+        stderr: err.to_string(),
+      })
+    }
+  };
+
+  match output.status.success() {
+    true => Ok(()),
+    false => Err(DriverError::ToolFailed {
+      tool: tool.to_owned(),
+      stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+    }),
+  }
+}
@@ -5,34 +5,178 @@ use std::fmt;
 #[derive(Clone, Debug)]
 pub enum Instruction {
   VarArgFunctionDeclaration(VarArgFunction),
-  PrintNumber(String),
+  /// Calls `printf` with the format string stored in the global `format`.
+  Print {
+    format: String,
+    format_length: usize,
+    args: Vec<(LLVMType, String)>,
+  },
+  FunctionDeclaration(ExternalFunction),
   GlobalVariableDeclaration(GlobalVariable),
   LocalVariableDeclaration(LocalVariable),
   // LoadLocalVariable {
   //   name: String,
   //   variable: LocalVariable,
   // },
+  Label(String),
+  Branch(String),
+  ConditionalBranch {
+    condition: String,
+    then_label: String,
+    else_label: String,
+  },
+  Call(String),
+  Alloca {
+    name: String,
+    value_type: LLVMType,
+  },
+  Load {
+    name: String,
+    value_type: LLVMType,
+    pointer: String,
+  },
+  Store {
+    value_type: LLVMType,
+    value: String,
+    pointer: String,
+  },
+  /// `incoming` holds `(value, label)` pairs, one for every predecessor block.
+  Phi {
+    name: String,
+    value_type: LLVMType,
+    incoming: Vec<(String, String)>,
+  },
+  Return {
+    value_type: LLVMType,
+    value: String,
+  },
+  ReturnVoid,
+  Unreachable,
   ReturnOk,
 }
 
 #[derive(Clone, Debug)]
 pub enum LLVMStatement {
   I32Literal(i32),
-  Addition(Box<LLVMStatement>, Box<LLVMStatement>),
+  I1Literal(bool),
+  /// Arithmetic and bitwise instructions whose result has the same type as
+  /// their operands.
+  BinaryInstruction {
+    instruction: BinaryInstruction,
+    operand_type: LLVMType,
+    lhs: Box<LLVMStatement>,
+    rhs: Box<LLVMStatement>,
+  },
+  /// `icmp`, always evaluates to an `i1`.
+  Comparison {
+    predicate: ComparisonPredicate,
+    operand_type: LLVMType,
+    lhs: Box<LLVMStatement>,
+    rhs: Box<LLVMStatement>,
+  },
+  Select {
+    condition: Box<LLVMStatement>,
+    value_type: LLVMType,
+    then_value: Box<LLVMStatement>,
+    else_value: Box<LLVMStatement>,
+  },
+  /// A pointer to the first character of a string literal stored in the
+  /// global `name`, `length` includes the NUL terminator.
+  GlobalString {
+    name: String,
+    length: usize,
+  },
   VariableDeclaration(Box<Variable>),
+  /// A reference to a previously declared variable or parameter.
+  Variable(String),
+  Call {
+    identifier: String,
+    return_type: LLVMType,
+    args: Vec<(LLVMType, LLVMStatement)>,
+  },
+  /// `format` is the `printf` format string, before escaping.
   Print {
-    expr_type: LLVMType,
-    expr: Box<LLVMStatement>,
+    format: String,
+    args: Vec<(LLVMType, LLVMStatement)>,
   },
 }
 
+#[derive(Clone, Copy, Debug)]
+pub enum BinaryInstruction {
+  Add,
+  Sub,
+  Mul,
+  SDiv,
+  And,
+  Or,
+  Xor,
+}
+
+impl fmt::Display for BinaryInstruction {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      BinaryInstruction::Add => write!(f, "add"),
+      BinaryInstruction::Sub => write!(f, "sub"),
+      BinaryInstruction::Mul => write!(f, "mul"),
+      BinaryInstruction::SDiv => write!(f, "sdiv"),
+      BinaryInstruction::And => write!(f, "and"),
+      BinaryInstruction::Or => write!(f, "or"),
+      BinaryInstruction::Xor => write!(f, "xor"),
+    }
+  }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum ComparisonPredicate {
+  Eq,
+  Ne,
+  Slt,
+  Sle,
+  Sgt,
+  Sge,
+}
+
+impl fmt::Display for ComparisonPredicate {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      ComparisonPredicate::Eq => write!(f, "eq"),
+      ComparisonPredicate::Ne => write!(f, "ne"),
+      ComparisonPredicate::Slt => write!(f, "slt"),
+      ComparisonPredicate::Sle => write!(f, "sle"),
+      ComparisonPredicate::Sgt => write!(f, "sgt"),
+      ComparisonPredicate::Sge => write!(f, "sge"),
+    }
+  }
+}
+
 #[derive(Clone, Debug)]
 pub struct Variable {
   pub identifier: String,
   pub value_type: LLVMType,
+  /// Mutable variables are stored on the stack rather than as SSA values.
+  pub mutable: bool,
   pub rhs: LLVMStatement,
 }
 
+#[derive(Clone, Debug)]
+pub struct FunctionDefinition {
+  pub name: String,
+  pub return_type: LLVMType,
+  pub params: Vec<(String, LLVMType)>,
+  pub instructions: Vec<Instruction>,
+}
+
+impl FunctionDefinition {
+  pub fn main(instructions: Vec<Instruction>) -> Self {
+    Self {
+      name: "main".to_owned(),
+      return_type: LLVMType::I32,
+      params: Vec::new(),
+      instructions,
+    }
+  }
+}
+
 #[derive(Clone, Debug)]
 pub struct VarArgFunction {
   pub out_type: LLVMType,
@@ -58,6 +202,34 @@ impl VarArgFunction {
   }
 }
 
+/// A function defined outside of the program, e.g. in libc.
+#[derive(Clone, Debug)]
+pub struct ExternalFunction {
+  pub out_type: LLVMType,
+  pub name: String,
+  pub in_types: Vec<LLVMType>,
+}
+
+impl ExternalFunction {
+  pub fn strcmp() -> Self {
+    Self {
+      out_type: LLVMType::I32,
+      name: "strcmp".to_owned(),
+      in_types: vec![LLVMType::I8Ptr, LLVMType::I8Ptr],
+    }
+  }
+}
+
+/// Returns the names of the functions the code generator defines or declares
+/// itself, programs cannot use them for their own.
+pub fn generated_function_names() -> Vec<String> {
+  vec![
+    FunctionDefinition::main(Vec::new()).name,
+    VarArgFunction::printf().name,
+    ExternalFunction::strcmp().name,
+  ]
+}
+
 #[derive(Clone, Debug)]
 pub struct GlobalVariable {
   pub name: String,
@@ -84,16 +256,26 @@ impl GlobalVariable {
     }
   }
 
-  pub fn format_num() -> Self {
+  /// A NUL terminated string constant.
+  pub fn string(name: String, text: &str) -> Self {
+    let mut value = String::from("c\"");
+    for byte in text.bytes() {
+      match byte {
+        b' '..=b'~' if byte != b'"' && byte != b'\\' => value.push(byte as char),
+        _ => value.push_str(&format!("\\{:02X}", byte)),
+      }
+    }
+    value.push_str("\\00\"");
+
     Self {
-      name: "format_num".to_owned(),
+      name,
       access: AccessModifier::Private,
       is_constant: true,
       value_type: LLVMType::Array(Array {
-        count: 3,
+        count: text.len() as i32 + 1,
         value_type: Box::new(LLVMType::I8),
       }),
-      value: r#"c"%d\00""#.to_owned(),
+      value,
     }
   }
 }
@@ -131,9 +313,11 @@ impl Array {
 pub enum LLVMType {
   I8Ptr,
   I32Ptr,
+  I1,
   I8,
   I32,
   Array(Array),
+  Void,
 }
 
 impl fmt::Display for LLVMType {
@@ -141,9 +325,11 @@ impl fmt::Display for LLVMType {
     match self {
       LLVMType::I8Ptr => write!(f, "i8*"),
       LLVMType::I32Ptr => write!(f, "i32*"),
+      LLVMType::I1 => write!(f, "i1"),
       LLVMType::I8 => write!(f, "i8"),
       LLVMType::I32 => write!(f, "i32"),
       LLVMType::Array(arr) => write!(f, "[{} x {}]", arr.count, *arr.value_type),
+      LLVMType::Void => write!(f, "void"),
     }
   }
 }
@@ -1,16 +1,14 @@
-use super::llvm_context::{Instruction, LLVMStatement, LLVMType};
+use super::llvm_context::{FunctionDefinition, Instruction, LLVMStatement, LLVMType};
 
-/// Returns a string tuple for `(Global, Main)` instructions.
+/// Returns a string tuple for `(Global, Function)` instructions.
 /// This is very likely to change later as returning strings is weird.
 /// Can also return string error message but that shouldn't happen.
 pub(crate) fn stringify_instructions(
   global_instructions: &Vec<Instruction>,
-  main_instructions: &Vec<Instruction>,
+  functions: &Vec<FunctionDefinition>,
 ) -> Result<(String, String), String> {
   let mut global = String::new();
-  let mut main = String::new();
-
-  main.push_str("define i32 @main() {\n");
+  let mut definitions = Vec::<String>::new();
 
   for instruction in global_instructions {
     match instruction {
@@ -21,6 +19,16 @@ pub(crate) fn stringify_instructions(
         );
         global.push_str(&str);
       }
+      Instruction::FunctionDeclaration(func) => {
+        let in_types = func
+          .in_types
+          .iter()
+          .map(|in_type| in_type.to_string())
+          .collect::<Vec<String>>()
+          .join(", ");
+        let str = format!("declare {} @{}({})\n", func.out_type, func.name, in_types);
+        global.push_str(&str);
+      }
       Instruction::GlobalVariableDeclaration(var) => {
         let str = format!(
           "@{} = {} {} {} {}\n",
@@ -33,65 +41,239 @@ pub(crate) fn stringify_instructions(
         global.push_str(&str);
       }
 
-      Instruction::LocalVariableDeclaration(_) => todo!(),
-      Instruction::ReturnOk => todo!(),
-      Instruction::PrintNumber(_) => todo!(),
+      _ => return Err(format!("{:?} is not a global instruction", instruction)),
     }
   }
 
-  for instruction in main_instructions {
-    match instruction {
-      Instruction::PrintNumber(var) => {
-        let str = format!(
-          "call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([3 x i8], [3 x i8]* @format_num, i32 0, i32 0), i32 {})\n",
-          var
-        );
-        main.push_str(&str);
+  for function in functions {
+    definitions.push(stringify_function(function)?);
+  }
+
+  Ok((global, definitions.join("\n")))
+}
+
+/// Stringifies a function definition along with its body.
+fn stringify_function(function: &FunctionDefinition) -> Result<String, String> {
+  let params = function
+    .params
+    .iter()
+    .map(|(name, param_type)| format!("{} %{}", param_type, name))
+    .collect::<Vec<String>>()
+    .join(", ");
+
+  let mut res = format!(
+    "define {} @{}({}) {{\n",
+    function.return_type, function.name, params
+  );
+
+  for instruction in &function.instructions {
+    let str = match instruction {
+      Instruction::Print {
+        format,
+        format_length,
+        args,
+      } => {
+        let args = args
+          .iter()
+          .map(|(arg_type, arg)| format!(", {} {}", arg_type, arg))
+          .collect::<String>();
+
+        format!(
+          "call i32 (i8*, ...) @printf(i8* getelementptr inbounds ([{} x i8], [{} x i8]* @{}, i32 0, i32 0){})",
+          format_length, format_length, format, args
+        )
       }
-      Instruction::LocalVariableDeclaration(var) => {
-        let str = match var.value_type {
-          LLVMType::I8Ptr => todo!(),
-          LLVMType::I32Ptr => todo!(),
-          LLVMType::I8 => todo!(),
-          LLVMType::I32 => {
-            format!("%{} = {}\n", var.name, var.value)
-          }
-          LLVMType::Array(_) => todo!(),
-        };
-        main.push_str(&str);
+      Instruction::LocalVariableDeclaration(var) => match var.value_type {
+        LLVMType::I1 | LLVMType::I32 | LLVMType::I8Ptr => {
+          format!("%{} = {}", var.name, var.value)
+        }
+        LLVMType::I32Ptr => todo!(),
+        LLVMType::I8 => todo!(),
+        LLVMType::Array(_) => todo!(),
+        LLVMType::Void => return Err(format!("{} cannot be of type void", var.name)),
+      },
+      Instruction::Label(label) => {
+        // Labels are not indented.
+        res.push_str(&format!("{}:\n", label));
+        continue;
       }
-      Instruction::ReturnOk => main.push_str("ret i32 0"),
-      Instruction::VarArgFunctionDeclaration(func) => todo!(),
-      Instruction::GlobalVariableDeclaration(var) => todo!(),
-    }
+      Instruction::Branch(label) => format!("br label %{}", label),
+      Instruction::ConditionalBranch {
+        condition,
+        then_label,
+        else_label,
+      } => format!(
+        "br i1 {}, label %{}, label %{}",
+        condition, then_label, else_label
+      ),
+      Instruction::Call(call) => call.to_owned(),
+      Instruction::Alloca { name, value_type } => format!("%{} = alloca {}", name, value_type),
+      Instruction::Load {
+        name,
+        value_type,
+        pointer,
+      } => format!(
+        "%{} = load {}, {}* %{}",
+        name, value_type, value_type, pointer
+      ),
+      Instruction::Store {
+        value_type,
+        value,
+        pointer,
+      } => format!(
+        "store {} {}, {}* %{}",
+        value_type, value, value_type, pointer
+      ),
+      Instruction::Phi {
+        name,
+        value_type,
+        incoming,
+      } => {
+        let incoming = incoming
+          .iter()
+          .map(|(value, label)| format!("[ {}, %{} ]", value, label))
+          .collect::<Vec<String>>()
+          .join(", ");
+
+        format!("%{} = phi {} {}", name, value_type, incoming)
+      }
+      Instruction::Return { value_type, value } => format!("ret {} {}", value_type, value),
+      Instruction::ReturnVoid => "ret void".to_owned(),
+      Instruction::Unreachable => "unreachable".to_owned(),
+      Instruction::ReturnOk => "ret i32 0".to_owned(),
+      Instruction::VarArgFunctionDeclaration(_)
+      | Instruction::FunctionDeclaration(_)
+      | Instruction::GlobalVariableDeclaration(_) => {
+        return Err(format!("{:?} is not a local instruction", instruction))
+      }
+    };
+
+    res.push_str("  ");
+    res.push_str(&str);
+    res.push('\n');
   }
 
-  let mut main = main.replace('\n', "\n  ");
-  main.push_str("\n}\n");
+  res.push_str("}\n");
 
-  Ok((global, main))
+  Ok(res)
 }
 
-/// Stringifies a statement.
+/// Stringifies a statement. Prints are lowered to `Instruction::Print` instead,
+/// as they need a global format string.
 pub(crate) fn stringify_llvm_statement(expr: &LLVMStatement) -> String {
   match expr {
     LLVMStatement::I32Literal(n) => format!("add i32 {}, 0", n),
-    LLVMStatement::Print { expr_type, expr } => todo!(),
-    LLVMStatement::Addition(x, y) => format!(
-      "add i32 {}, {}",
-      stringify_llvm_numeric(x),
-      stringify_llvm_numeric(y)
+    LLVMStatement::I1Literal(b) => format!("or i1 {}, false", b),
+    LLVMStatement::Print { format: _, args: _ } => {
+      unreachable!("Prints are lowered to `Instruction::Print`")
+    }
+    LLVMStatement::BinaryInstruction {
+      instruction,
+      operand_type,
+      lhs,
+      rhs,
+    } => format!(
+      "{} {} {}, {}",
+      instruction,
+      operand_type,
+      stringify_llvm_operand(lhs),
+      stringify_llvm_operand(rhs)
+    ),
+    LLVMStatement::Comparison {
+      predicate,
+      operand_type,
+      lhs,
+      rhs,
+    } => format!(
+      "icmp {} {} {}, {}",
+      predicate,
+      operand_type,
+      stringify_llvm_operand(lhs),
+      stringify_llvm_operand(rhs)
+    ),
+    LLVMStatement::Select {
+      condition,
+      value_type,
+      then_value,
+      else_value,
+    } => format!(
+      "select i1 {}, {} {}, {} {}",
+      stringify_llvm_operand(condition),
+      value_type,
+      stringify_llvm_operand(then_value),
+      value_type,
+      stringify_llvm_operand(else_value)
+    ),
+    LLVMStatement::GlobalString { name, length } => format!(
+      "getelementptr inbounds [{} x i8], [{} x i8]* @{}, i32 0, i32 0",
+      length, length, name
     ),
     LLVMStatement::VariableDeclaration(v) => v.identifier.to_owned(),
+    LLVMStatement::Variable(identifier) => identifier.to_owned(),
+    LLVMStatement::Call {
+      identifier,
+      return_type,
+      args,
+    } => {
+      let args = args
+        .iter()
+        .map(|(arg_type, arg)| format!("{} {}", arg_type, stringify_llvm_operand(arg)))
+        .collect::<Vec<String>>()
+        .join(", ");
+
+      format!("call {} @{}({})", return_type, identifier, args)
+    }
   }
 }
 
-/// Stringifies a numeric statement.
-pub(crate) fn stringify_llvm_numeric(expr: &LLVMStatement) -> String {
+/// Stringifies a statement that can be used directly as an operand
+/// (a literal or a variable), see `is_llvm_operand`. Any other statement has
+/// to be materialized into a temporary first.
+pub(crate) fn stringify_llvm_operand(expr: &LLVMStatement) -> String {
   match expr {
     LLVMStatement::I32Literal(n) => n.to_string(),
-    LLVMStatement::Addition(x, y) => todo!(),
+    LLVMStatement::I1Literal(b) => b.to_string(),
+    LLVMStatement::BinaryInstruction {
+      instruction: _,
+      operand_type: _,
+      lhs: _,
+      rhs: _,
+    } => unreachable!("Instructions must be materialized first"),
+    LLVMStatement::Comparison {
+      predicate: _,
+      operand_type: _,
+      lhs: _,
+      rhs: _,
+    } => unreachable!("Instructions must be materialized first"),
+    LLVMStatement::Select {
+      condition: _,
+      value_type: _,
+      then_value: _,
+      else_value: _,
+    } => unreachable!("Instructions must be materialized first"),
+    LLVMStatement::GlobalString { name, length } => format!(
+      "getelementptr inbounds ([{} x i8], [{} x i8]* @{}, i32 0, i32 0)",
+      length, length, name
+    ),
     LLVMStatement::VariableDeclaration(v) => "%".to_owned() + &v.identifier,
-    LLVMStatement::Print { expr_type, expr } => todo!(),
+    LLVMStatement::Variable(identifier) => "%".to_owned() + identifier,
+    LLVMStatement::Call {
+      identifier: _,
+      return_type: _,
+      args: _,
+    } => unreachable!("Calls must be materialized first"),
+    LLVMStatement::Print { format: _, args: _ } => unreachable!("Prints are not values"),
   }
 }
+
+/// Returns whether the statement can be passed to `stringify_llvm_operand`.
+pub(crate) fn is_llvm_operand(expr: &LLVMStatement) -> bool {
+  matches!(
+    expr,
+    LLVMStatement::I32Literal(_)
+      | LLVMStatement::I1Literal(_)
+      | LLVMStatement::GlobalString { name: _, length: _ }
+      | LLVMStatement::VariableDeclaration(_)
+      | LLVMStatement::Variable(_)
+  )
+}
@@ -1,10 +1,19 @@
 use crate::{
-  bind::bound_ast::{BoundBinaryOp, BoundExpr, BoundProgram, BoundStatement, Type},
-  code_gen::llvm_context::{GlobalVariable, LocalVariable, VarArgFunction},
+  bind::{
+    binder::Binder,
+    bound_ast::{
+      BoundBinaryOp, BoundExpr, BoundProgram, BoundStatement, BoundUnaryOp, Symbol, Type,
+    },
+  },
+  code_gen::llvm_context::{
+    ExternalFunction, FunctionDefinition, GlobalVariable, LocalVariable, VarArgFunction,
+  },
 };
 
 use super::{
-  llvm_context::{Instruction, LLVMStatement, LLVMType, Variable},
+  llvm_context::{
+    BinaryInstruction, ComparisonPredicate, Instruction, LLVMStatement, LLVMType, Variable,
+  },
   llvm_ir_builder,
 };
 
@@ -13,18 +22,12 @@ impl LLVMProgramBuilder {
     Self::default()
   }
 
-  pub fn generate_llvm(&mut self, prog: BoundProgram) {
+  /// Lowers the program and returns the textual LLVM module.
+  pub fn generate_llvm(&mut self, prog: BoundProgram) -> Result<String, String> {
     let statements = match prog {
       BoundProgram::Body { stmts } => stmts,
     };
 
-    let exprs: Vec<BoundExpr> = statements
-      .iter()
-      .map(|x| match x {
-        BoundStatement::BoundExpr { expr } => expr.to_owned(),
-      })
-      .collect();
-
     if !self.main_instructions.is_empty() {
       // remove main
       self
@@ -32,135 +35,674 @@ impl LLVMProgramBuilder {
         .remove(self.main_instructions.len() - 1);
     }
 
-    for expr in exprs {
-      let instruction = Self::bound_expr_to_llvm(expr.clone());
-      match instruction {
-        LLVMStatement::I32Literal(_) => todo!(),
-        LLVMStatement::Addition(_, _) => todo!(),
-        LLVMStatement::Print { expr_type, expr } => {
-          if !self.print_information.include {
-            self
-              .global_instructions
-              .push(Instruction::VarArgFunctionDeclaration(
-                VarArgFunction::printf(),
-              ));
-            self.print_information.include = true;
-
-            match expr_type {
-              LLVMType::I8Ptr => todo!(),
-              LLVMType::I32Ptr => todo!(),
-              LLVMType::I8 => todo!(),
-              LLVMType::I32 => match self.print_information.include_num {
-                true => {}
-                false => {
-                  let format_num = GlobalVariable::format_num();
-
-                  self
-                    .global_instructions
-                    .push(Instruction::GlobalVariableDeclaration(format_num));
-                  self.print_information.include_num = true;
-                }
-              },
-              LLVMType::Array(_) => todo!(),
-            }
-          }
-          let str = llvm_ir_builder::stringify_llvm_statement(&expr);
-          let str = match *expr {
-            LLVMStatement::VariableDeclaration(_) => "%",
-            _ => "",
-          }
-          .to_owned()
-            + &str;
+    for statement in statements {
+      self.statement_to_llvm(statement);
+    }
+
+    self.main_instructions.push(Instruction::ReturnOk);
+
+    self.build()
+  }
+
+  /// Lowers a statement, appending the resulting instructions to `main`
+  /// (or to the function currently being generated).
+  fn statement_to_llvm(&mut self, statement: BoundStatement) {
+    match statement {
+      BoundStatement::BoundExpr { expr } => self.expr_statement_to_llvm(expr),
+      BoundStatement::BoundIf {
+        condition,
+        then_body,
+        else_body,
+      } => {
+        let id = self.next_label_id();
+        let then_label = format!("if.then{}", id);
+        let else_label = format!("if.else{}", id);
+        let end_label = format!("if.end{}", id);
+
+        let condition = self.bound_expr_to_llvm(condition);
+        let condition = self.llvm_operand(condition, LLVMType::I1);
 
-          self.main_instructions.push(Instruction::PrintNumber(str))
+        self.main_instructions.push(Instruction::ConditionalBranch {
+          condition,
+          then_label: then_label.to_owned(),
+          else_label: match else_body {
+            Some(_) => else_label.to_owned(),
+            None => end_label.to_owned(),
+          },
+        });
+
+        self.main_instructions.push(Instruction::Label(then_label));
+        for statement in then_body {
+          self.statement_to_llvm(statement);
         }
-        LLVMStatement::VariableDeclaration(variable) => {
-          let name = variable.identifier;
-          let value_type = variable.value_type;
-          let value = llvm_ir_builder::stringify_llvm_statement(&variable.rhs);
+        self
+          .main_instructions
+          .push(Instruction::Branch(end_label.to_owned()));
 
-          let var = LocalVariable {
-            name,
-            value_type,
-            value,
-          };
+        if let Some(else_body) = else_body {
+          self.main_instructions.push(Instruction::Label(else_label));
+          for statement in else_body {
+            self.statement_to_llvm(statement);
+          }
           self
             .main_instructions
-            .push(Instruction::LocalVariableDeclaration(var))
+            .push(Instruction::Branch(end_label.to_owned()));
+        }
+
+        self.main_instructions.push(Instruction::Label(end_label));
+      }
+      BoundStatement::BoundWhile { condition, body } => {
+        let id = self.next_label_id();
+        let cond_label = format!("while.cond{}", id);
+        let body_label = format!("while.body{}", id);
+        let end_label = format!("while.end{}", id);
+
+        self
+          .main_instructions
+          .push(Instruction::Branch(cond_label.to_owned()));
+        self
+          .main_instructions
+          .push(Instruction::Label(cond_label.to_owned()));
+
+        let condition = self.bound_expr_to_llvm(condition);
+        let condition = self.llvm_operand(condition, LLVMType::I1);
+
+        self.main_instructions.push(Instruction::ConditionalBranch {
+          condition,
+          then_label: body_label.to_owned(),
+          else_label: end_label.to_owned(),
+        });
+
+        self.main_instructions.push(Instruction::Label(body_label));
+        self
+          .loop_labels
+          .push((cond_label.to_owned(), end_label.to_owned()));
+        for statement in body {
+          self.statement_to_llvm(statement);
         }
+        self.loop_labels.pop();
+        self.main_instructions.push(Instruction::Branch(cond_label));
+
+        self.main_instructions.push(Instruction::Label(end_label));
+      }
+      BoundStatement::BoundBreak { source_pos: _ } => {
+        // The binder guarantees we are inside of a loop.
+        if let Some((_, end_label)) = self.loop_labels.last() {
+          let end_label = end_label.to_owned();
+          self.terminate_block(Instruction::Branch(end_label));
+        }
+      }
+      BoundStatement::BoundContinue { source_pos: _ } => {
+        if let Some((cond_label, _)) = self.loop_labels.last() {
+          let cond_label = cond_label.to_owned();
+          self.terminate_block(Instruction::Branch(cond_label));
+        }
+      }
+      BoundStatement::BoundFunction {
+        identifier,
+        params,
+        return_type,
+        body,
+        source_pos: _,
+      } => {
+        // The body is generated in place of `main` which is swapped back in afterwards.
+        let main_instructions = std::mem::take(&mut self.main_instructions);
+
+        for statement in body {
+          self.statement_to_llvm(statement);
+        }
+
+        // The binder makes sure non void functions return on every path, any
+        // block that is left open at this point can therefore never be reached.
+        self.main_instructions.push(match return_type {
+          Type::Void => Instruction::ReturnVoid,
+          _ => Instruction::Unreachable,
+        });
+
+        let instructions = std::mem::replace(&mut self.main_instructions, main_instructions);
+        let params = params
+          .into_iter()
+          .map(|param| {
+            let name = Self::symbol_to_llvm_name(&param);
+            (name, Self::type_to_llvm_type(param.value_type))
+          })
+          .collect();
+
+        self.functions.push(FunctionDefinition {
+          name: identifier,
+          return_type: Self::type_to_llvm_type(return_type),
+          params,
+          instructions,
+        });
+      }
+      BoundStatement::BoundReturn {
+        expr,
+        source_pos: _,
+      } => match expr {
+        Some(expr) if Binder::get_type(&expr) != Type::Void => {
+          let value_type = Self::type_to_llvm_type(Binder::get_type(&expr));
+          let value = self.bound_expr_to_llvm(expr);
+          let value = self.llvm_operand(value, value_type.to_owned());
+
+          self.terminate_block(Instruction::Return { value_type, value });
+        }
+        Some(expr) => {
+          self.expr_statement_to_llvm(expr);
+          self.terminate_block(Instruction::ReturnVoid);
+        }
+        None => self.terminate_block(Instruction::ReturnVoid),
+      },
+      BoundStatement::BoundAssignment {
+        symbol,
+        rhs,
+        source_pos: _,
+      } => {
+        let value_type = Self::type_to_llvm_type(symbol.value_type.to_owned());
+        let value = self.bound_expr_to_llvm(rhs);
+        let value = self.llvm_operand(value, value_type.to_owned());
+
+        self.main_instructions.push(Instruction::Store {
+          value_type,
+          value,
+          pointer: Self::symbol_to_llvm_name(&symbol),
+        });
       }
     }
+  }
 
-    self.main_instructions.push(Instruction::ReturnOk);
+  /// Pushes a terminator instruction (branch, return, ...). Any code following it
+  /// is placed in a new (unreachable) block as LLVM does not allow instructions
+  /// after a terminator.
+  fn terminate_block(&mut self, terminator: Instruction) {
+    let id = self.next_label_id();
 
-    println!("========");
+    self.main_instructions.push(terminator);
+    self
+      .main_instructions
+      .push(Instruction::Label(format!("unreachable{}", id)));
+  }
 
-    let tmp = self.build();
+  /// Selects either `"true"` or `"false"` depending on the value of a boolean
+  /// operand and returns the resulting string operand.
+  fn bool_to_string(&mut self, condition: LLVMStatement) -> LLVMStatement {
+    let true_str = GlobalVariable::string("str.true".to_owned(), "true");
+    let false_str = GlobalVariable::string("str.false".to_owned(), "false");
 
-    match tmp {
-      Ok(tmp) => println!("{}", tmp),
-      Err(_) => todo!(),
+    if !self.print_information.include_bool {
+      self
+        .global_instructions
+        .push(Instruction::GlobalVariableDeclaration(true_str));
+      self
+        .global_instructions
+        .push(Instruction::GlobalVariableDeclaration(false_str));
+      self.print_information.include_bool = true;
     }
 
-    println!("========");
+    let select = LLVMStatement::Select {
+      condition: Box::new(condition),
+      value_type: LLVMType::I8Ptr,
+      then_value: Box::new(LLVMStatement::GlobalString {
+        name: "str.true".to_owned(),
+        length: 5,
+      }),
+      else_value: Box::new(LLVMStatement::GlobalString {
+        name: "str.false".to_owned(),
+        length: 6,
+      }),
+    };
+    self.materialize(select, LLVMType::I8Ptr)
   }
 
-  fn bound_expr_to_llvm(expr: BoundExpr) -> LLVMStatement {
-    match expr {
-      BoundExpr::Int { n } => LLVMStatement::I32Literal(n),
-      BoundExpr::BoundDeclaration {
-        identifier,
+  /// Calls `strcmp` on two strings and returns the result as an operand.
+  fn strcmp_to_llvm(&mut self, lhs: LLVMStatement, rhs: LLVMStatement) -> LLVMStatement {
+    if !self.include_strcmp {
+      self
+        .global_instructions
+        .push(Instruction::FunctionDeclaration(ExternalFunction::strcmp()));
+      self.include_strcmp = true;
+    }
+
+    let call = LLVMStatement::Call {
+      identifier: "strcmp".to_owned(),
+      return_type: LLVMType::I32,
+      args: vec![(LLVMType::I8Ptr, lhs), (LLVMType::I8Ptr, rhs)],
+    };
+    self.materialize(call, LLVMType::I32)
+  }
+
+  /// Ends the current block with a branch to a new block named `label`. This
+  /// gives the code that follows a name that `phi` nodes can refer to.
+  fn start_block(&mut self, label: String) {
+    self
+      .main_instructions
+      .push(Instruction::Branch(label.to_owned()));
+    self.main_instructions.push(Instruction::Label(label));
+  }
+
+  /// Lowers `&&` and `||` so that the right-hand side is only evaluated if the
+  /// left-hand side does not already determine the result.
+  fn short_circuit_to_llvm(
+    &mut self,
+    op: BoundBinaryOp,
+    lhs: BoundExpr,
+    rhs: BoundExpr,
+  ) -> LLVMStatement {
+    let id = self.next_label_id();
+    let (prefix, short_circuit_value) = match op {
+      BoundBinaryOp::And => ("and", false),
+      _ => ("or", true),
+    };
+    let lhs_label = format!("{}.lhs{}", prefix, id);
+    let rhs_label = format!("{}.rhs{}", prefix, id);
+    let rhs_end_label = format!("{}.rhs.end{}", prefix, id);
+    let end_label = format!("{}.end{}", prefix, id);
+
+    // Either operand may itself contain branches, so their results are taken
+    // from dedicated blocks instead of the ones they started in.
+    let lhs = self.bound_expr_to_llvm(lhs);
+    let condition = self.llvm_operand(lhs, LLVMType::I1);
+    self.start_block(lhs_label.to_owned());
+
+    let (then_label, else_label) = match op {
+      BoundBinaryOp::And => (rhs_label.to_owned(), end_label.to_owned()),
+      _ => (end_label.to_owned(), rhs_label.to_owned()),
+    };
+    self.main_instructions.push(Instruction::ConditionalBranch {
+      condition,
+      then_label,
+      else_label,
+    });
+
+    self.main_instructions.push(Instruction::Label(rhs_label));
+    let rhs = self.bound_expr_to_llvm(rhs);
+    let rhs = self.llvm_operand(rhs, LLVMType::I1);
+    self.start_block(rhs_end_label.to_owned());
+    self.start_block(end_label);
+
+    let name = self.next_temporary();
+    self.main_instructions.push(Instruction::Phi {
+      name: name.to_owned(),
+      value_type: LLVMType::I1,
+      incoming: vec![
+        (short_circuit_value.to_string(), lhs_label),
+        (rhs, rhs_end_label),
+      ],
+    });
+
+    LLVMStatement::Variable(name)
+  }
+
+  /// Returns the operand for a statement, storing it in a local variable first if
+  /// it cannot be used as an operand directly.
+  fn llvm_operand(&mut self, statement: LLVMStatement, value_type: LLVMType) -> String {
+    let operand = self.materialize(statement, value_type);
+    llvm_ir_builder::stringify_llvm_operand(&operand)
+  }
+
+  /// Stores a statement that cannot be used as an operand directly in a fresh
+  /// temporary and returns a reference to it. Operands are returned as is.
+  fn materialize(&mut self, statement: LLVMStatement, value_type: LLVMType) -> LLVMStatement {
+    if llvm_ir_builder::is_llvm_operand(&statement) {
+      return statement;
+    }
+
+    let name = self.next_temporary();
+    let value = llvm_ir_builder::stringify_llvm_statement(&statement);
+    self
+      .main_instructions
+      .push(Instruction::LocalVariableDeclaration(LocalVariable::new(
+        name.to_owned(),
         value_type,
-        rhs,
+        value,
+      )));
+
+    LLVMStatement::Variable(name)
+  }
+
+  /// Lowers a (possibly nested) expression into an operand. Every intermediate
+  /// result is stored in its own temporary.
+  fn bound_expr_to_operand(&mut self, expr: BoundExpr) -> LLVMStatement {
+    let value_type = Self::type_to_llvm_type(Binder::get_type(&expr));
+    let statement = self.bound_expr_to_llvm(expr);
+    self.materialize(statement, value_type)
+  }
+
+  /// Lowers an expression that is used as a statement.
+  fn expr_statement_to_llvm(&mut self, expr: BoundExpr) {
+    let value_type = Self::type_to_llvm_type(Binder::get_type(&expr));
+    let instruction = self.bound_expr_to_llvm(expr);
+    match instruction {
+      // The value is unused but any nested calls still need to be evaluated.
+      value @ (LLVMStatement::I32Literal(_)
+      | LLVMStatement::I1Literal(_)
+      | LLVMStatement::BinaryInstruction {
+        instruction: _,
+        operand_type: _,
+        lhs: _,
+        rhs: _,
+      }
+      | LLVMStatement::Comparison {
+        predicate: _,
+        operand_type: _,
+        lhs: _,
+        rhs: _,
+      }
+      | LLVMStatement::Select {
+        condition: _,
+        value_type: _,
+        then_value: _,
+        else_value: _,
+      }
+      | LLVMStatement::GlobalString { name: _, length: _ }
+      | LLVMStatement::Variable(_)) => {
+        self.materialize(value, value_type);
+      }
+      call @ LLVMStatement::Call {
+        identifier: _,
+        return_type: _,
+        args: _,
       } => {
-        let value_type = Self::type_to_llvm_type(value_type);
-        let variable = Variable {
-          identifier,
+        let call = llvm_ir_builder::stringify_llvm_statement(&call);
+        self.main_instructions.push(Instruction::Call(call))
+      }
+      LLVMStatement::Print { format, args } => {
+        if !self.print_information.include {
+          self
+            .global_instructions
+            .push(Instruction::VarArgFunctionDeclaration(
+              VarArgFunction::printf(),
+            ));
+          self.print_information.include = true;
+        }
+
+        // Every call site gets its own format string.
+        let name = format!("fmt.{}", self.format_count);
+        self.format_count += 1;
+
+        let format_length = format.len() + 1;
+        self
+          .global_instructions
+          .push(Instruction::GlobalVariableDeclaration(
+            GlobalVariable::string(name.to_owned(), &format),
+          ));
+
+        let args = args
+          .iter()
+          .map(|(arg_type, arg)| {
+            (
+              arg_type.to_owned(),
+              llvm_ir_builder::stringify_llvm_operand(arg),
+            )
+          })
+          .collect();
+        self.main_instructions.push(Instruction::Print {
+          format: name,
+          format_length,
+          args,
+        })
+      }
+      LLVMStatement::VariableDeclaration(variable) if variable.mutable => {
+        let value_type = variable.value_type;
+        let pointer = variable.identifier;
+
+        // Allocas are placed in the entry block so that declarations inside of
+        // loops don't grow the stack on every iteration.
+        self.main_instructions.insert(
+          0,
+          Instruction::Alloca {
+            name: pointer.to_owned(),
+            value_type: value_type.to_owned(),
+          },
+        );
+
+        let value = self.llvm_operand(variable.rhs, value_type.to_owned());
+        self.main_instructions.push(Instruction::Store {
+          value_type,
+          value,
+          pointer,
+        });
+      }
+      LLVMStatement::VariableDeclaration(variable) => {
+        let name = variable.identifier;
+        let value_type = variable.value_type;
+        let value = match variable.rhs {
+          // Variables are not instructions on their own, copy them with a no-op cast.
+          rhs @ LLVMStatement::Variable(_) => format!(
+            "bitcast {} {} to {}",
+            value_type,
+            llvm_ir_builder::stringify_llvm_operand(&rhs),
+            value_type
+          ),
+          rhs => llvm_ir_builder::stringify_llvm_statement(&rhs),
+        };
+
+        let var = LocalVariable {
+          name,
           value_type,
-          rhs: Self::bound_expr_to_llvm(*rhs),
+          value,
+        };
+        self
+          .main_instructions
+          .push(Instruction::LocalVariableDeclaration(var))
+      }
+    }
+  }
+
+  /// Returns a new, unique name for a temporary value.
+  fn next_temporary(&mut self) -> String {
+    let name = format!("t{}", self.temporary_count);
+    self.temporary_count += 1;
+    name
+  }
+
+  /// Returns a new id to be used for uniquely naming labels.
+  fn next_label_id(&mut self) -> usize {
+    let id = self.label_count;
+    self.label_count += 1;
+    id
+  }
+
+  fn bound_expr_to_llvm(&mut self, expr: BoundExpr) -> LLVMStatement {
+    match expr {
+      BoundExpr::Int { n, source_pos: _ } => LLVMStatement::I32Literal(n),
+      BoundExpr::BoundDeclaration { symbol, rhs } => {
+        let variable = Variable {
+          identifier: Self::symbol_to_llvm_name(&symbol),
+          value_type: Self::type_to_llvm_type(symbol.value_type),
+          mutable: symbol.mutable,
+          rhs: self.bound_expr_to_llvm(*rhs),
         };
         LLVMStatement::VariableDeclaration(Box::new(variable))
       }
-      BoundExpr::Bool { b } => todo!(),
-      BoundExpr::String { str } => todo!(),
+      BoundExpr::Bool { b, source_pos: _ } => LLVMStatement::I1Literal(b),
+      BoundExpr::String { str, source_pos: _ } => {
+        let name = format!("str.{}", self.string_count);
+        self.string_count += 1;
+
+        // Strings are NUL terminated.
+        let length = str.len() + 1;
+        let string = GlobalVariable::string(name.to_owned(), &str);
+        self
+          .global_instructions
+          .push(Instruction::GlobalVariableDeclaration(string));
+
+        LLVMStatement::GlobalString { name, length }
+      }
       BoundExpr::BoundBinaryOp {
         op,
         lhs,
         rhs,
-        bin_op_type: _,
+        bin_op_type,
+        source_pos: _,
       } => match op {
-        BoundBinaryOp::Add => {
-          let lhs = Box::new(Self::bound_expr_to_llvm(*lhs));
-          let rhs = Box::new(Self::bound_expr_to_llvm(*rhs));
-          LLVMStatement::Addition(lhs, rhs)
-        }
-        BoundBinaryOp::Sub => todo!(),
-        BoundBinaryOp::Mul => todo!(),
-        BoundBinaryOp::Div => todo!(),
-        BoundBinaryOp::And => todo!(),
-        BoundBinaryOp::Or => todo!(),
+        BoundBinaryOp::Add | BoundBinaryOp::Sub | BoundBinaryOp::Mul | BoundBinaryOp::Div => {
+          let instruction = match op {
+            BoundBinaryOp::Add => BinaryInstruction::Add,
+            BoundBinaryOp::Sub => BinaryInstruction::Sub,
+            BoundBinaryOp::Mul => BinaryInstruction::Mul,
+            _ => BinaryInstruction::SDiv,
+          };
+          let operand_type = Self::type_to_llvm_type(bin_op_type);
+          let lhs = Box::new(self.bound_expr_to_operand(*lhs));
+          let rhs = Box::new(self.bound_expr_to_operand(*rhs));
+
+          LLVMStatement::BinaryInstruction {
+            instruction,
+            operand_type,
+            lhs,
+            rhs,
+          }
+        }
+        BoundBinaryOp::And | BoundBinaryOp::Or => self.short_circuit_to_llvm(op, *lhs, *rhs),
+        BoundBinaryOp::Eq
+        | BoundBinaryOp::Ne
+        | BoundBinaryOp::Lt
+        | BoundBinaryOp::Le
+        | BoundBinaryOp::Gt
+        | BoundBinaryOp::Ge => {
+          let predicate = match op {
+            BoundBinaryOp::Eq => ComparisonPredicate::Eq,
+            BoundBinaryOp::Ne => ComparisonPredicate::Ne,
+            BoundBinaryOp::Lt => ComparisonPredicate::Slt,
+            BoundBinaryOp::Le => ComparisonPredicate::Sle,
+            BoundBinaryOp::Gt => ComparisonPredicate::Sgt,
+            _ => ComparisonPredicate::Sge,
+          };
+          let operand_type = Self::type_to_llvm_type(Binder::get_type(&lhs));
+          let lhs = self.bound_expr_to_operand(*lhs);
+          let rhs = self.bound_expr_to_operand(*rhs);
+
+          // Strings are compared by their contents, `strcmp` returns 0 if they are equal.
+          let (operand_type, lhs, rhs) = match operand_type {
+            LLVMType::I8Ptr => (
+              LLVMType::I32,
+              self.strcmp_to_llvm(lhs, rhs),
+              LLVMStatement::I32Literal(0),
+            ),
+            _ => (operand_type, lhs, rhs),
+          };
+          let lhs = Box::new(lhs);
+          let rhs = Box::new(rhs);
+
+          LLVMStatement::Comparison {
+            predicate,
+            operand_type,
+            lhs,
+            rhs,
+          }
+        }
       },
-      BoundExpr::ParenthesizedExpression { expr } => todo!(),
-      BoundExpr::BoundPrint { expr_type, expr } => {
-        let expr_type = Self::type_to_llvm_type(expr_type);
-        let expr = Box::new(Self::bound_expr_to_llvm(*expr));
+      BoundExpr::BoundUnaryOp {
+        op,
+        expr,
+        source_pos: _,
+      } => {
+        let expr = Box::new(self.bound_expr_to_operand(*expr));
+
+        // Both are lowered as binary instructions, `sub i32 0, x` and `xor i1 x, true`.
+        match op {
+          BoundUnaryOp::Neg => LLVMStatement::BinaryInstruction {
+            instruction: BinaryInstruction::Sub,
+            operand_type: LLVMType::I32,
+            lhs: Box::new(LLVMStatement::I32Literal(0)),
+            rhs: expr,
+          },
+          BoundUnaryOp::Not => LLVMStatement::BinaryInstruction {
+            instruction: BinaryInstruction::Xor,
+            operand_type: LLVMType::I1,
+            lhs: expr,
+            rhs: Box::new(LLVMStatement::I1Literal(true)),
+          },
+        }
+      }
+      BoundExpr::ParenthesizedExpression { expr } => self.bound_expr_to_llvm(*expr),
+      BoundExpr::BoundPrint {
+        pieces,
+        args,
+        newline,
+        source_pos: _,
+      } => {
+        // `%` has to be escaped as the pieces become part of a `printf` format.
+        let mut format = pieces[0].replace('%', "%%");
+        let mut llvm_args = Vec::new();
 
-        LLVMStatement::Print { expr_type, expr }
+        for (arg, piece) in args.into_iter().zip(pieces.iter().skip(1)) {
+          let arg_type = Self::type_to_llvm_type(Binder::get_type(&arg));
+          let arg = self.bound_expr_to_operand(arg);
+
+          let (specifier, arg_type, arg) = match arg_type {
+            LLVMType::I1 => ("%s", LLVMType::I8Ptr, self.bool_to_string(arg)),
+            LLVMType::I32 => ("%d", arg_type, arg),
+            _ => ("%s", arg_type, arg),
+          };
+
+          format.push_str(specifier);
+          format.push_str(&piece.replace('%', "%%"));
+          llvm_args.push((arg_type, arg));
+        }
+
+        if newline {
+          format.push('\n');
+        }
+
+        LLVMStatement::Print {
+          format,
+          args: llvm_args,
+        }
+      }
+      BoundExpr::BoundVariable {
+        symbol,
+        source_pos: _,
+      } if symbol.mutable => {
+        // Mutable variables live on the stack and need to be loaded before every use.
+        let name = self.next_temporary();
+        self.main_instructions.push(Instruction::Load {
+          name: name.to_owned(),
+          value_type: Self::type_to_llvm_type(symbol.value_type.to_owned()),
+          pointer: Self::symbol_to_llvm_name(&symbol),
+        });
+
+        LLVMStatement::Variable(name)
+      }
+      BoundExpr::BoundVariable {
+        symbol,
+        source_pos: _,
+      } => LLVMStatement::Variable(Self::symbol_to_llvm_name(&symbol)),
+      BoundExpr::BoundCall {
+        identifier,
+        args,
+        return_type,
+        source_pos: _,
+      } => {
+        let args = args
+          .into_iter()
+          .map(|arg| {
+            let arg_type = Self::type_to_llvm_type(Binder::get_type(&arg));
+            (arg_type, self.bound_expr_to_operand(arg))
+          })
+          .collect();
+
+        LLVMStatement::Call {
+          identifier,
+          return_type: Self::type_to_llvm_type(return_type),
+          args,
+        }
       }
+      BoundExpr::Error { source_pos: _ } => unreachable!("Programs with errors are not compiled"),
     }
   }
 
   fn build(&self) -> Result<String, String> {
     let mut res = String::new();
 
-    match llvm_ir_builder::stringify_instructions(
-      &self.global_instructions,
-      &self.main_instructions,
-    ) {
-      Ok((global, main)) => {
+    let mut functions = self.functions.to_owned();
+    functions.push(FunctionDefinition::main(self.main_instructions.to_owned()));
+
+    match llvm_ir_builder::stringify_instructions(&self.global_instructions, &functions) {
+      Ok((global, functions)) => {
         res.push_str(&global);
         res.push('\n');
-        res.push_str(&main);
+        res.push_str(&functions);
 
         Ok(res)
       }
@@ -168,11 +710,19 @@ impl LLVMProgramBuilder {
     }
   }
 
+  /// Returns the name of the LLVM value holding the symbol. Names include the
+  /// symbol's id as shadowed variables would otherwise clash.
+  fn symbol_to_llvm_name(symbol: &Symbol) -> String {
+    format!("{}.{}", symbol.name, symbol.id)
+  }
+
   fn type_to_llvm_type(value_type: Type) -> LLVMType {
     match value_type {
       Type::Int => LLVMType::I32,
-      Type::Bool => todo!(),
-      Type::String => todo!(),
+      Type::Bool => LLVMType::I1,
+      Type::String => LLVMType::I8Ptr,
+      Type::Void => LLVMType::Void,
+      Type::Error => unreachable!("Programs with errors are not compiled"),
     }
   }
 }
@@ -182,15 +732,100 @@ pub struct LLVMProgramBuilder {
   print_information: PrintInformation,
   global_instructions: Vec<Instruction>,
   main_instructions: Vec<Instruction>,
+  functions: Vec<FunctionDefinition>,
+  label_count: usize,
+  temporary_count: usize,
+  /// Used for uniquely naming the globals holding string literals.
+  string_count: usize,
+  /// Used for uniquely naming the format string of every `print` call.
+  format_count: usize,
+  include_strcmp: bool,
+  /// `(continue, break)` labels of the loops enclosing the current statement.
+  loop_labels: Vec<(String, String)>,
 }
 
 #[derive(Default)]
 struct PrintInformation {
   include: bool,
-  include_num: bool,
+  include_bool: bool,
 }
 
 #[derive(Debug)]
 pub struct LLVMProgram {
   pub code: String,
 }
+
+#[cfg(test)]
+mod tests {
+  use crate::{bind::bound_ast::BoundProgram, bind_sources};
+
+  use super::LLVMProgramBuilder;
+
+  fn compile(source: &str) -> String {
+    compile_modules(&[("main", source)])
+  }
+
+  /// Compiles the `(name, source)` modules, the first one being the entry.
+  fn compile_modules(sources: &[(&str, &str)]) -> String {
+    let BoundProgram::Body { stmts } = bind_sources(sources).expect("Source should bind");
+
+    let mut builder = LLVMProgramBuilder::new();
+    for statement in stmts {
+      builder.statement_to_llvm(statement);
+    }
+
+    builder.build().expect("Program should build")
+  }
+
+  fn position(ir: &str, needle: &str) -> usize {
+    ir.find(needle)
+      .unwrap_or_else(|| panic!("`{}` not found in:\n{}", needle, ir))
+  }
+
+  #[test]
+  fn and_skips_rhs_if_lhs_is_false() {
+    let ir = compile("fn f() -> bool { return true; } let x = false && f();");
+
+    // `false` jumps straight to the end, past the block that calls `f`.
+    assert!(ir.contains("br i1 false, label %and.rhs1, label %and.end1"));
+    assert!(position(&ir, "and.rhs1:") < position(&ir, "call i1 @f()"));
+    assert!(position(&ir, "call i1 @f()") < position(&ir, "and.end1:"));
+    assert!(ir.contains("phi i1 [ false, %and.lhs1 ], [ %t0, %and.rhs.end1 ]"));
+  }
+
+  #[test]
+  fn or_skips_rhs_if_lhs_is_true() {
+    let ir = compile("fn f() -> bool { return true; } let x = true || f();");
+
+    // `true` jumps straight to the end, past the block that calls `f`.
+    assert!(ir.contains("br i1 true, label %or.end1, label %or.rhs1"));
+    assert!(position(&ir, "or.rhs1:") < position(&ir, "call i1 @f()"));
+    assert!(position(&ir, "call i1 @f()") < position(&ir, "or.end1:"));
+    assert!(ir.contains("phi i1 [ true, %or.lhs1 ], [ %t0, %or.rhs.end1 ]"));
+  }
+
+  #[test]
+  fn nested_short_circuits_use_their_own_blocks() {
+    let ir = compile("let a = true; let b = a && a || a;");
+
+    // The inner `&&` ends in its own block, which the `||` branches from.
+    assert!(ir.contains("phi i1 [ false, %and.lhs1 ], [ %a.0, %and.rhs.end1 ]"));
+    assert!(ir.contains("phi i1 [ true, %or.lhs0 ], [ %a.0, %or.rhs.end0 ]"));
+    assert!(position(&ir, "and.end1:") < position(&ir, "or.lhs0:"));
+  }
+
+  #[test]
+  fn imported_functions_are_mangled() {
+    let ir = compile_modules(&[
+      (
+        "main",
+        "import math; fn square() {} let x = math::square(2);",
+      ),
+      ("math", "pub fn square(x: int) -> int { return x * x; }"),
+    ]);
+
+    assert!(ir.contains("define i32 @mod.math.square(i32 %x.0)"));
+    assert!(ir.contains("define void @square()"));
+    assert!(ir.contains("call i32 @mod.math.square(i32 2)"));
+  }
+}
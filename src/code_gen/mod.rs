@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+pub mod driver;
 pub mod llvm_context;
 pub mod llvm_ir_builder;
 pub mod llvm_module;
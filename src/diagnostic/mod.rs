@@ -0,0 +1,163 @@
+pub mod parse_error;
+pub mod render;
+
+use std::fmt;
+
+use crate::ast::SourcePos;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning,
+  Note,
+}
+
+impl fmt::Display for Severity {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Severity::Error => write!(f, "error"),
+      Severity::Warning => write!(f, "warning"),
+      Severity::Note => write!(f, "note"),
+    }
+  }
+}
+
+/// Identifies the kind of a diagnostic. The codes are stable: new kinds get a
+/// new code and existing codes are never reused for something else.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiagnosticCode {
+  // Parser
+  UnexpectedEof,
+  UnrecognizedToken,
+  InvalidToken,
+  ExtraToken,
+  InvalidSyntax,
+  // Binder
+  UndefinedVariable,
+  DuplicateVariable,
+  ImmutableAssignment,
+  VoidValue,
+  InvalidOperand,
+  MismatchedTypes,
+  IntegerOutOfRange,
+  UndefinedFunction,
+  DuplicateFunction,
+  ReservedFunctionName,
+  NestedFunction,
+  DuplicateParameter,
+  ArgumentCount,
+  MissingReturn,
+  ReturnOutsideFunction,
+  OutsideLoop,
+  InvalidFormatString,
+  UnknownModule,
+  ImportCycle,
+  PrivateFunction,
+  // Interpreter
+  DivisionByZero,
+  DivisionOverflow,
+  CallDepthExceeded,
+}
+
+impl fmt::Display for DiagnosticCode {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let code = match self {
+      DiagnosticCode::UnexpectedEof => "E0001",
+      DiagnosticCode::UnrecognizedToken => "E0002",
+      DiagnosticCode::InvalidToken => "E0003",
+      DiagnosticCode::ExtraToken => "E0004",
+      DiagnosticCode::InvalidSyntax => "E0005",
+      DiagnosticCode::UndefinedVariable => "E0100",
+      DiagnosticCode::DuplicateVariable => "E0101",
+      DiagnosticCode::ImmutableAssignment => "E0102",
+      DiagnosticCode::VoidValue => "E0103",
+      DiagnosticCode::InvalidOperand => "E0104",
+      DiagnosticCode::MismatchedTypes => "E0105",
+      DiagnosticCode::IntegerOutOfRange => "E0106",
+      DiagnosticCode::UndefinedFunction => "E0107",
+      DiagnosticCode::DuplicateFunction => "E0108",
+      DiagnosticCode::ReservedFunctionName => "E0109",
+      DiagnosticCode::NestedFunction => "E0110",
+      DiagnosticCode::DuplicateParameter => "E0111",
+      DiagnosticCode::ArgumentCount => "E0112",
+      DiagnosticCode::MissingReturn => "E0113",
+      DiagnosticCode::ReturnOutsideFunction => "E0114",
+      DiagnosticCode::OutsideLoop => "E0115",
+      DiagnosticCode::InvalidFormatString => "E0116",
+      DiagnosticCode::UnknownModule => "E0117",
+      DiagnosticCode::ImportCycle => "E0118",
+      DiagnosticCode::PrivateFunction => "E0119",
+      DiagnosticCode::DivisionByZero => "E0200",
+      DiagnosticCode::DivisionOverflow => "E0201",
+      DiagnosticCode::CallDepthExceeded => "E0202",
+    };
+
+    write!(f, "{}", code)
+  }
+}
+
+/// A message attached to a position in the source.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Label {
+  pub source_pos: SourcePos,
+  /// May be empty, in which case only the position is highlighted.
+  pub msg: String,
+}
+
+/// An error (or warning) reported to the user. The primary label points at
+/// the cause, secondary labels at related positions, e.g. a previous
+/// declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+  pub severity: Severity,
+  pub code: DiagnosticCode,
+  pub msg: String,
+  pub primary_label: Label,
+  pub secondary_labels: Vec<Label>,
+  pub help: Option<String>,
+}
+
+impl Diagnostic {
+  pub fn new(
+    severity: Severity,
+    code: DiagnosticCode,
+    msg: impl Into<String>,
+    source_pos: SourcePos,
+  ) -> Self {
+    Self {
+      severity,
+      code,
+      msg: msg.into(),
+      primary_label: Label {
+        source_pos,
+        msg: String::new(),
+      },
+      secondary_labels: Vec::new(),
+      help: None,
+    }
+  }
+
+  pub fn error(code: DiagnosticCode, msg: impl Into<String>, source_pos: SourcePos) -> Self {
+    Self::new(Severity::Error, code, msg, source_pos)
+  }
+
+  /// Sets the message of the primary label.
+  pub fn with_primary_label(mut self, msg: impl Into<String>) -> Self {
+    self.primary_label.msg = msg.into();
+    self
+  }
+
+  /// Adds a secondary label.
+  pub fn with_label(mut self, source_pos: SourcePos, msg: impl Into<String>) -> Self {
+    self.secondary_labels.push(Label {
+      source_pos,
+      msg: msg.into(),
+    });
+    self
+  }
+
+  pub fn with_help(mut self, help: impl Into<String>) -> Self {
+    self.help = Some(help.into());
+    self
+  }
+}
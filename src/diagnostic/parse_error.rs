@@ -0,0 +1,548 @@
+// turns the errors reported by lalrpop into diagnostics
+
+use lalrpop_util::{lexer::Token, ParseError};
+
+use crate::{
+  ast::SourcePos,
+  parse,
+  parser::{is_unknown, ProgramParser},
+  source_map::{FileId, SourceFile},
+};
+
+use super::{Diagnostic, DiagnosticCode};
+
+/// Tokens that can continue an expression.
+const OPERATORS: [&str; 12] = [
+  "+", "-", "*", "/", "&&", "||", "==", "!=", "<", "<=", ">", ">=",
+];
+
+/// Tokens that can only start a statement.
+const STATEMENT_KEYWORDS: [&str; 9] = [
+  "let", "if", "while", "break", "continue", "fn", "return", "pub", "import",
+];
+
+/// Tokens that can start an expression, besides literals and identifiers.
+const EXPRESSION_TOKENS: [&str; 7] = ["(", "-", "!", "true", "false", "print(", "println("];
+
+/// How the expected set of tokens is described.
+#[derive(PartialEq, Eq)]
+enum Expected {
+  Token(String),
+  Operator,
+  Statement,
+  Expression,
+}
+
+impl Diagnostic {
+  /// Converts an error `parser` reported for `file` into a diagnostic.
+  pub fn from_parse_error(
+    parser: &ProgramParser,
+    file: &SourceFile,
+    err: &ParseError<usize, Token<'_>, &str>,
+  ) -> Self {
+    let input = file.source();
+    let accepted_terminals = |location: usize, expected: &[String]| {
+      accepted_terminals(parser, file.id(), input, location, expected)
+    };
+
+    match err {
+      ParseError::InvalidToken { location } => {
+        let len = input[*location..].chars().next().map_or(0, char::len_utf8);
+        unknown_token(file.id(), *location, &input[*location..location + len])
+      }
+      // Lexed so the parser can recover from it, but reported the same way.
+      ParseError::UnrecognizedToken {
+        token: (start, token, _),
+        expected: _,
+      } if is_unknown(token) => unknown_token(file.id(), *start, token.1),
+      ParseError::UnrecognizedEOF { location, expected } => {
+        let expected = describe_expected(&accepted_terminals(input.len(), expected));
+        let source_pos = SourcePos {
+          file: file.id(),
+          start: *location,
+          end: location + 1,
+        };
+
+        let err = Diagnostic::error(
+          DiagnosticCode::UnexpectedEof,
+          format!("Expected {}, found end of file.", join(&expected)),
+          source_pos,
+        );
+
+        match expected.as_slice() {
+          [Expected::Token(token)] => err.with_help(format!("try adding `{}`", token)),
+          _ => err,
+        }
+      }
+      ParseError::UnrecognizedToken {
+        token: (start, token, end),
+        expected,
+      } => {
+        let expected = describe_expected(&accepted_terminals(*start, expected));
+        let source_pos = SourcePos {
+          file: file.id(),
+          start: *start,
+          end: *end,
+        };
+
+        let err = Diagnostic::error(
+          DiagnosticCode::UnrecognizedToken,
+          format!("Expected {}, found `{}`.", join(&expected), token.1),
+          source_pos,
+        )
+        .with_primary_label(format!("unexpected `{}`", token.1));
+
+        match expected.as_slice() {
+          // Missing tokens, e.g. a `;`, usually belong right after the
+          // previous token rather than in front of the unexpected one.
+          [Expected::Token(expected)] => {
+            let insert_at = input[..*start].trim_end().len();
+            let err = err.with_help(format!("try adding `{}`", expected));
+
+            match insert_at < *start {
+              true => err.with_label(
+                SourcePos {
+                  file: file.id(),
+                  start: insert_at,
+                  end: insert_at,
+                },
+                format!("expected `{}` here", expected),
+              ),
+              false => err,
+            }
+          }
+          _ => err,
+        }
+      }
+      ParseError::ExtraToken {
+        token: (start, token, end),
+      } => Diagnostic::error(
+        DiagnosticCode::ExtraToken,
+        format!("Unexpected `{}` after the end of the program.", token.1),
+        SourcePos {
+          file: file.id(),
+          start: *start,
+          end: *end,
+        },
+      ),
+      // The grammar does not produce errors of its own, this is only reached
+      // if the lexer does.
+      ParseError::User { error } => Diagnostic::error(
+        DiagnosticCode::InvalidSyntax,
+        error.to_string(),
+        SourcePos {
+          file: file.id(),
+          start: 0,
+          end: 0,
+        },
+      ),
+    }
+  }
+}
+
+/// Describes `text`, starting at `start`, that no token matches. It is either
+/// an unknown character or a string the string literal token rejects.
+fn unknown_token(file: FileId, start: usize, text: &str) -> Diagnostic {
+  let c = text.chars().next().unwrap_or(' ');
+  let (msg, len) = match c {
+    '"' if text.contains('\'') => ("Strings cannot contain `'`.".to_owned(), text.len()),
+    '"' => ("Unterminated string literal.".to_owned(), text.len()),
+    // Escaped as they would not show up otherwise, e.g. `\u{7}`.
+    c if c.is_control() => (format!("Unknown character `{}`.", c.escape_default()), 1),
+    c => (format!("Unknown character `{}`.", c), c.len_utf8()),
+  };
+
+  Diagnostic::error(
+    DiagnosticCode::InvalidToken,
+    msg,
+    SourcePos {
+      file,
+      start,
+      end: start + len,
+    },
+  )
+}
+
+/// Returns the expected terminals that can actually be inserted at `location`.
+/// lalrpop merges similar parser states, so the terminals it reports include
+/// ones that are only valid in other contexts, e.g. `)` after `let x = 1`.
+/// It also reduces the expression before reporting the error, only expecting
+/// the operators of the lowest precedence, so every operator is tried if one
+/// is expected. Each terminal is tried by parsing the statement containing
+/// `location`, up to it, with the terminal appended.
+fn accepted_terminals(
+  parser: &ProgramParser,
+  file: FileId,
+  input: &str,
+  location: usize,
+  expected: &[String],
+) -> Vec<String> {
+  let mut candidates = expected.to_vec();
+  let expects_operator = expected
+    .iter()
+    .any(|terminal| OPERATORS.contains(&describe_terminal(terminal).as_str()));
+  if expects_operator {
+    for operator in OPERATORS {
+      let terminal = format!("\"{}\"", operator);
+      if !candidates.contains(&terminal) {
+        candidates.push(terminal);
+      }
+    }
+  }
+
+  // Statements in blocks are parsed inside of one so that `}` is accepted.
+  let (start, in_block) = statement_start(input, location);
+  let prefix = match in_block {
+    true => "while true { ",
+    false => "",
+  };
+  let statement = format!("{}{}", prefix, &input[start..location]);
+  let location = statement.len();
+
+  let accepted = candidates
+    .into_iter()
+    .filter(|terminal| {
+      // Whatever follows the terminal does not affect whether it is accepted.
+      let input = format!("{} {}", statement, terminal_sample(terminal));
+
+      // Errors before the appended terminal are the same as in the input.
+      let error_location = parse(parser, file, &input)
+        .1
+        .iter()
+        .map(|err| match err {
+          ParseError::InvalidToken { location } => *location,
+          ParseError::UnrecognizedEOF {
+            location,
+            expected: _,
+          } => *location,
+          ParseError::UnrecognizedToken { token, expected: _ } => token.0,
+          ParseError::ExtraToken { token } => token.0,
+          ParseError::User { error: _ } => 0,
+        })
+        .find(|error_location| *error_location >= location);
+
+      // The sample starts after the inserted space, running out of input
+      // right after it is reported at its end.
+      error_location.is_none_or(|error_location| error_location > location + 1)
+    })
+    .collect::<Vec<String>>();
+
+  // Should never happen, but the full list beats an empty one.
+  match accepted.is_empty() {
+    true => expected.to_vec(),
+    false => accepted,
+  }
+}
+
+/// Returns where the statement containing `location` starts and whether it is
+/// inside of a block. Blocks ending in between are part of the statement if
+/// nothing but an `else` follows them, e.g. in `if a {} else`.
+fn statement_start(input: &str, location: usize) -> (usize, bool) {
+  let input = &input[..location];
+  let mut start = 0;
+  // The starts of the statements containing the blocks the scan is in.
+  let mut outer_starts = Vec::<usize>::new();
+  // Set right after a block ends, the statement ends there unless an `else`
+  // follows.
+  let mut block_end = false;
+  let mut chars = input.char_indices().peekable();
+
+  while let Some((i, c)) = chars.next() {
+    if c.is_whitespace() {
+      continue;
+    }
+
+    if block_end {
+      block_end = false;
+      let word = input[i..]
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .next()
+        .unwrap_or("");
+      if word != "else" {
+        start = i;
+      }
+    }
+
+    match c {
+      '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => {
+        while chars.next_if(|(_, c)| *c != '\n').is_some() {}
+      }
+      '"' => while chars.next().is_some_and(|(_, c)| c != '"') {},
+      ';' => start = i + 1,
+      '{' => {
+        outer_starts.push(start);
+        start = i + 1;
+      }
+      '}' => match outer_starts.pop() {
+        Some(outer_start) => {
+          start = outer_start;
+          block_end = true;
+        }
+        None => start = i + 1,
+      },
+      _ => {}
+    }
+  }
+
+  (start, !outer_starts.is_empty())
+}
+
+/// Describes the terminals lalrpop expected, grouping operators, expression
+/// starts and statement keywords.
+fn describe_expected(expected: &[String]) -> Vec<Expected> {
+  let tokens = expected
+    .iter()
+    .map(|terminal| describe_terminal(terminal))
+    .collect::<Vec<String>>();
+  let contains = |group: &[&str]| group.iter().any(|token| tokens.iter().any(|t| t == token));
+  let operator_count = tokens
+    .iter()
+    .filter(|token| OPERATORS.contains(&token.as_str()))
+    .count();
+
+  let mut res = Vec::<Expected>::new();
+  let mut push = |expected: Expected| {
+    if !res.contains(&expected) {
+      res.push(expected)
+    }
+  };
+
+  let expects_statement = contains(&STATEMENT_KEYWORDS);
+  let expects_expression = contains(&["integer", "string literal", "identifier"]);
+
+  for token in tokens.iter() {
+    let token = token.as_str();
+
+    if expects_statement
+      && (STATEMENT_KEYWORDS.contains(&token) || EXPRESSION_TOKENS.contains(&token))
+    {
+      push(Expected::Statement)
+    } else if expects_expression && EXPRESSION_TOKENS.contains(&token) {
+      push(Expected::Expression)
+    } else if operator_count > 1 && OPERATORS.contains(&token) {
+      push(Expected::Operator)
+    } else {
+      match token {
+        "integer" | "string literal" | "identifier" if expects_statement => {
+          push(Expected::Statement)
+        }
+        "integer" | "string literal" | "identifier" => push(Expected::Expression),
+        _ => push(Expected::Token(token.to_owned())),
+      }
+    }
+  }
+
+  res
+}
+
+/// Turns the name lalrpop uses for a terminal, e.g. `";"` or `r#"[0-9]+"#`,
+/// into the name shown to the user.
+fn describe_terminal(terminal: &str) -> String {
+  match terminal {
+    r###"r#"[0-9]+"#"### => "integer".to_owned(),
+    r###"r#"\"[^\"']*\""#"### => "string literal".to_owned(),
+    r###"r#"[a-z][a-z0-9]*"#"### => "identifier".to_owned(),
+    _ => terminal.trim_matches('"').to_owned(),
+  }
+}
+
+/// Returns source text that is lexed as the terminal.
+fn terminal_sample(terminal: &str) -> String {
+  match describe_terminal(terminal).as_str() {
+    "integer" => "0".to_owned(),
+    "string literal" => "\"\"".to_owned(),
+    "identifier" => "x".to_owned(),
+    token => token.to_owned(),
+  }
+}
+
+/// Joins the expected tokens into e.g. "`;`, `)` or operator".
+fn join(expected: &[Expected]) -> String {
+  let names = expected
+    .iter()
+    .map(|expected| match expected {
+      Expected::Token(token) => format!("`{}`", token),
+      Expected::Operator => "operator".to_owned(),
+      Expected::Statement => "statement".to_owned(),
+      Expected::Expression => "expression".to_owned(),
+    })
+    .collect::<Vec<String>>();
+
+  match names.split_last() {
+    None => "nothing".to_owned(),
+    Some((last, [])) => last.to_owned(),
+    Some((last, rest)) => format!("{} or {}", rest.join(", "), last),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use lalrpop_util::{lexer::Token, ParseError};
+
+  use crate::{
+    ast::SourcePos,
+    diagnostic::{Diagnostic, DiagnosticCode, Label},
+    parse,
+    parser::ProgramParser,
+    source_map::SourceMap,
+  };
+
+  use super::statement_start;
+
+  /// Converts `err`, or the first syntax error of the source if it is `None`.
+  fn convert(source: &str, err: Option<ParseError<usize, Token<'_>, &str>>) -> Diagnostic {
+    let parser = ProgramParser::new();
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main", source);
+    let err = err.unwrap_or_else(|| {
+      parse(&parser, file, source)
+        .1
+        .into_iter()
+        .next()
+        .expect("Source should have a syntax error")
+    });
+
+    Diagnostic::from_parse_error(&parser, source_map.get(file), &err)
+  }
+
+  fn diagnostic(source: &str) -> Diagnostic {
+    convert(source, None)
+  }
+
+  #[test]
+  fn invalid_token() {
+    let err = diagnostic("let a = 1 $ 2;");
+
+    assert_eq!(err.code, DiagnosticCode::InvalidToken);
+    assert_eq!(err.msg, "Unknown character `$`.");
+    assert_eq!(
+      (
+        err.primary_label.source_pos.start,
+        err.primary_label.source_pos.end
+      ),
+      (10, 11)
+    );
+  }
+
+  #[test]
+  fn only_invisible_characters_are_escaped() {
+    assert_eq!(diagnostic("let a = \\;").msg, "Unknown character `\\`.");
+    assert_eq!(
+      diagnostic("let a = \u{7};").msg,
+      "Unknown character `\\u{7}`."
+    );
+  }
+
+  #[test]
+  fn unterminated_string() {
+    let err = diagnostic("print(\"abc);\nlet a = 1;");
+
+    assert_eq!(err.code, DiagnosticCode::InvalidToken);
+    assert_eq!(err.msg, "Unterminated string literal.");
+    assert_eq!(
+      (
+        err.primary_label.source_pos.start,
+        err.primary_label.source_pos.end
+      ),
+      (6, 12)
+    );
+  }
+
+  #[test]
+  fn unrecognized_eof() {
+    let err = diagnostic("let a = 1 + 2");
+
+    assert_eq!(err.code, DiagnosticCode::UnexpectedEof);
+    assert_eq!(err.msg, "Expected operator or `;`, found end of file.");
+    assert_eq!(err.help, None);
+  }
+
+  #[test]
+  fn unrecognized_token_expects_every_operator() {
+    let err = diagnostic("let a = (1 + 2;");
+
+    assert_eq!(err.code, DiagnosticCode::UnrecognizedToken);
+    assert_eq!(err.msg, "Expected `)` or operator, found `;`.");
+  }
+
+  #[test]
+  fn extra_token() {
+    let err = convert(
+      "}",
+      Some(ParseError::ExtraToken {
+        token: (0, Token(0, "}"), 1),
+      }),
+    );
+
+    assert_eq!(err.code, DiagnosticCode::ExtraToken);
+    assert_eq!(err.msg, "Unexpected `}` after the end of the program.");
+  }
+
+  #[test]
+  fn user_error() {
+    let err = convert(
+      "",
+      Some(ParseError::User {
+        error: "Invalid input",
+      }),
+    );
+
+    assert_eq!(err.code, DiagnosticCode::InvalidSyntax);
+    assert_eq!(err.msg, "Invalid input");
+  }
+
+  #[test]
+  fn single_expected_token_is_suggested() {
+    let err = diagnostic("import math let x = 1;");
+
+    assert_eq!(err.msg, "Expected `;`, found `let`.");
+    assert_eq!(err.help.as_deref(), Some("try adding `;`"));
+    assert_eq!(
+      err.secondary_labels,
+      vec![Label {
+        source_pos: SourcePos {
+          file: err.primary_label.source_pos.file,
+          start: 11,
+          end: 11,
+        },
+        msg: "expected `;` here".to_owned(),
+      }]
+    );
+
+    let err = diagnostic("import math");
+    assert_eq!(err.msg, "Expected `;`, found end of file.");
+    assert_eq!(err.help.as_deref(), Some("try adding `;`"));
+  }
+
+  #[test]
+  fn expected_terminals_only_depend_on_the_statement() {
+    let prefix = "fn f() { if true {} else { print(\"{;\"); } }\n// { ;\n".repeat(100);
+
+    for statement in ["let a = (1 + 2;", "let e = 1 +;", "import math let x = 1;"] {
+      let alone = diagnostic(statement);
+      let err = diagnostic(&format!("{}{}", prefix, statement));
+
+      assert_eq!(err.msg, alone.msg);
+      assert_eq!(err.help, alone.help);
+    }
+
+    assert_eq!(
+      diagnostic("fn f() { let a = 1; if a {} ").msg,
+      "Expected statement or `}`, found end of file."
+    );
+  }
+
+  #[test]
+  fn statements_start_after_the_previous_one() {
+    fn start(input: &str) -> (&str, bool) {
+      let (start, in_block) = statement_start(input, input.len());
+      (&input[start..], in_block)
+    }
+
+    assert_eq!(start("let a = 1; let b = (1 +"), (" let b = (1 +", false));
+    assert_eq!(start("fn f() { let b ="), (" let b =", true));
+    assert_eq!(start("if a {} else { b; } c"), ("c", false));
+    assert_eq!(start("while a { if b {} else"), (" if b {} else", true));
+    assert_eq!(start("let s = \"{\"; // {\nx"), (" // {\nx", false));
+  }
+}
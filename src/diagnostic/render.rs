@@ -0,0 +1,390 @@
+// renders diagnostics in the style of rustc:
+//
+// error[E0101]: Variable identifier is already taken
+//  --> main.rs:3:5
+//   |
+// 1 | let x = 1;
+//   |     - first declared here
+// 2 | let y = 2;
+// 3 | let x = 3;
+//   |     ^ 'x' redeclared here
+
+use std::{collections::BTreeMap, io::IsTerminal};
+
+use crate::source_map::{FileId, SourceFile, SourceMap};
+
+use super::{Diagnostic, Label, Severity};
+
+const TAB_WIDTH: usize = 4;
+
+// https://gist.github.com/fnky/458719343aabd01cfb17a3a4f7296797
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const CYAN: &str = "\x1b[1;36m";
+const BLUE: &str = "\x1b[1;34m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether diagnostics are colored.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColorChoice {
+  Never,
+  Always,
+  /// Only color if stderr is a terminal and `NO_COLOR` is not set.
+  Auto,
+}
+
+impl ColorChoice {
+  /// Returns whether output written to `stream` should be colored.
+  pub fn enabled(self, stream: &impl IsTerminal) -> bool {
+    match self {
+      ColorChoice::Never => false,
+      ColorChoice::Always => true,
+      ColorChoice::Auto => stream.is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+    }
+  }
+}
+
+pub struct Renderer {
+  color: bool,
+}
+
+/// The part of a single source line covered by a label, in display columns.
+struct Annotation {
+  start: usize,
+  end: usize,
+  primary: bool,
+  /// Only set on the last line of the label.
+  msg: String,
+}
+
+/// The lines of a single file covered by the labels of a diagnostic.
+struct Snippet<'a> {
+  file: &'a SourceFile,
+  /// Where the first label in the file starts.
+  offset: usize,
+  annotations: BTreeMap<usize, Vec<Annotation>>,
+}
+
+impl Renderer {
+  pub fn new(color: ColorChoice) -> Self {
+    Self {
+      color: color.enabled(&std::io::stderr()),
+    }
+  }
+
+  /// Renders the diagnostic, the files its labels point into are looked up in
+  /// `source_map`.
+  pub fn render(&self, source_map: &SourceMap, diagnostic: &Diagnostic) -> String {
+    let severity_style = match diagnostic.severity {
+      Severity::Error => RED,
+      Severity::Warning => YELLOW,
+      Severity::Note => CYAN,
+    };
+
+    // The snippet of the primary label's file comes first, the others follow
+    // in the order their files were added.
+    let primary_file = diagnostic.primary_label.source_pos.file;
+    let mut snippets = BTreeMap::<(bool, FileId), Snippet<'_>>::new();
+    let labels = std::iter::once((&diagnostic.primary_label, true)).chain(
+      diagnostic
+        .secondary_labels
+        .iter()
+        .map(|label| (label, false)),
+    );
+    for (label, primary) in labels {
+      let file = source_map.get(label.source_pos.file);
+      snippets
+        .entry((file.id() != primary_file, file.id()))
+        .or_insert_with(|| Snippet {
+          file,
+          offset: label.source_pos.start,
+          annotations: BTreeMap::new(),
+        })
+        .annotate(label, primary);
+    }
+
+    let last_line = snippets
+      .values()
+      .filter_map(|snippet| snippet.annotations.keys().last())
+      .max()
+      .map_or(0, |line| line + 1);
+    let width = last_line.to_string().len();
+    let gutter = self.paint(BLUE, &format!("{} |", " ".repeat(width)));
+
+    let mut res = format!(
+      "{}: {}\n",
+      self.paint(
+        severity_style,
+        &format!("{}[{}]", diagnostic.severity, diagnostic.code)
+      ),
+      self.paint(BOLD, &diagnostic.msg),
+    );
+
+    for ((other_file, _), snippet) in snippets {
+      let (line, column) = snippet.file.location(snippet.offset);
+      res.push_str(&format!(
+        "{}{} {}:{}:{}\n{}\n",
+        " ".repeat(width),
+        self.paint(BLUE, if other_file { ":::" } else { "-->" }),
+        snippet.file.name(),
+        line + 1,
+        column,
+        gutter
+      ));
+
+      let mut previous_line = None;
+      for (line, mut line_annotations) in snippet.annotations {
+        // A single line between two snippets is shown rather than elided.
+        match previous_line.map(|previous| line - previous) {
+          Some(2) => res.push_str(&self.render_line(snippet.file, line - 1, width)),
+          Some(distance) if distance > 2 => res.push_str(&format!("{}\n", self.paint(BLUE, "..."))),
+          _ => {}
+        }
+        res.push_str(&self.render_line(snippet.file, line, width));
+
+        line_annotations.sort_by_key(|annotation| (annotation.start, !annotation.primary));
+        for annotation in line_annotations {
+          let (marker, style) = match annotation.primary {
+            true => ("^", severity_style),
+            false => ("-", BLUE),
+          };
+          let mut underline = marker.repeat(annotation.end - annotation.start);
+          if !annotation.msg.is_empty() {
+            underline.push(' ');
+            underline.push_str(&annotation.msg);
+          }
+
+          res.push_str(&format!(
+            "{} {}{}\n",
+            gutter,
+            " ".repeat(annotation.start),
+            self.paint(style, &underline)
+          ));
+        }
+
+        previous_line = Some(line);
+      }
+    }
+
+    if let Some(help) = &diagnostic.help {
+      res.push_str(&format!(
+        "{} {} {}: {}\n",
+        " ".repeat(width),
+        self.paint(BLUE, "="),
+        self.paint(BOLD, "help"),
+        help
+      ));
+    }
+
+    res
+  }
+
+  fn render_line(&self, file: &SourceFile, line: usize, width: usize) -> String {
+    let text = file.line(line).replace('\t', &" ".repeat(TAB_WIDTH));
+    let number = self.paint(BLUE, &format!("{:>width$} |", line + 1));
+
+    match text.is_empty() {
+      true => format!("{}\n", number),
+      false => format!("{} {}\n", number, text),
+    }
+  }
+
+  fn paint(&self, style: &str, text: &str) -> String {
+    match self.color {
+      true => format!("{}{}{}", style, text, RESET),
+      false => text.to_owned(),
+    }
+  }
+}
+
+impl Snippet<'_> {
+  /// Splits the label into an annotation for every line it covers.
+  fn annotate(&mut self, label: &Label, primary: bool) {
+    let file = self.file;
+    let start = file.clamp(label.source_pos.start);
+    let end = file.clamp(label.source_pos.end).max(start);
+    let start_line = file.line_index(start);
+    // The end is exclusive, a span ending with a newline ends on that line.
+    let end_line = file.line_index(end.saturating_sub(1).max(start));
+
+    for line in start_line..=end_line {
+      let text = file.line(line);
+      let line_start = file.line_start(line);
+
+      let from = match line == start_line {
+        true => display_width(&text[..(start - line_start).min(text.len())]),
+        false => display_width(&text[..text.len() - text.trim_start().len()]),
+      };
+      let to = match line == end_line {
+        true => display_width(&text[..(end - line_start).min(text.len())]),
+        false => display_width(text),
+      };
+
+      self.annotations.entry(line).or_default().push(Annotation {
+        start: from,
+        end: to.max(from + 1),
+        primary,
+        msg: match line == end_line {
+          true => label.msg.to_owned(),
+          false => String::new(),
+        },
+      });
+    }
+  }
+}
+
+/// The number of columns the text takes up once printed.
+fn display_width(text: &str) -> usize {
+  text.chars().map(char_width).sum()
+}
+
+/// The number of columns a character takes up in a terminal. Only the common
+/// combining, zero width and wide (mostly CJK and emoji) ranges are covered,
+/// everything else is assumed to be a single column wide.
+fn char_width(c: char) -> usize {
+  match c {
+    '\t' => TAB_WIDTH,
+    '\0'..='\x1f'
+    | '\x7f'..='\u{9f}'
+    | '\u{300}'..='\u{36f}'
+    | '\u{200b}'..='\u{200f}'
+    | '\u{fe00}'..='\u{fe0f}'
+    | '\u{feff}' => 0,
+    '\u{1100}'..='\u{115f}'
+    | '\u{2e80}'..='\u{303e}'
+    | '\u{3041}'..='\u{33ff}'
+    | '\u{3400}'..='\u{4dbf}'
+    | '\u{4e00}'..='\u{9fff}'
+    | '\u{a000}'..='\u{a4cf}'
+    | '\u{ac00}'..='\u{d7a3}'
+    | '\u{f900}'..='\u{faff}'
+    | '\u{fe30}'..='\u{fe4f}'
+    | '\u{ff00}'..='\u{ff60}'
+    | '\u{ffe0}'..='\u{ffe6}'
+    | '\u{1f300}'..='\u{1f64f}'
+    | '\u{1f900}'..='\u{1f9ff}'
+    | '\u{20000}'..='\u{2fffd}'
+    | '\u{30000}'..='\u{3fffd}' => 2,
+    _ => 1,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{
+    ast::SourcePos,
+    diagnostic::{Diagnostic, DiagnosticCode},
+    source_map::{FileId, SourceMap},
+  };
+
+  use super::{ColorChoice, Renderer};
+
+  fn render(source_map: &SourceMap, diagnostic: &Diagnostic) -> String {
+    Renderer::new(ColorChoice::Never).render(source_map, diagnostic)
+  }
+
+  fn pos(file: FileId, start: usize, end: usize) -> SourcePos {
+    SourcePos { file, start, end }
+  }
+
+  #[test]
+  fn labels_on_different_lines() {
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main.rs", "let x = 1;\nlet y = 2;\nlet x = 3;\n");
+    let diagnostic = Diagnostic::error(
+      DiagnosticCode::DuplicateVariable,
+      "Variable identifier is already taken",
+      pos(file, 26, 27),
+    )
+    .with_primary_label("'x' redeclared here")
+    .with_label(pos(file, 4, 5), "first declared here")
+    .with_help("rename one of them");
+
+    assert_eq!(
+      render(&source_map, &diagnostic),
+      "error[E0101]: Variable identifier is already taken
+ --> main.rs:3:5
+  |
+1 | let x = 1;
+  |     - first declared here
+2 | let y = 2;
+3 | let x = 3;
+  |     ^ 'x' redeclared here
+  = help: rename one of them
+"
+    );
+  }
+
+  #[test]
+  fn columns_account_for_tabs_and_wide_characters() {
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main.rs", "\tprint(\"日本\" + 1);");
+    let diagnostic = Diagnostic::error(
+      DiagnosticCode::InvalidOperand,
+      "Cannot perform '+' between String and Int.",
+      pos(file, 16, 17),
+    );
+
+    assert_eq!(
+      render(&source_map, &diagnostic),
+      "error[E0104]: Cannot perform '+' between String and Int.
+ --> main.rs:1:13
+  |
+1 |     print(\"日本\" + 1);
+  |                  ^
+"
+    );
+  }
+
+  #[test]
+  fn spans_across_lines() {
+    let mut source_map = SourceMap::new();
+    let file = source_map.add("main.rs", "print(1 +\n  true);");
+    let diagnostic = Diagnostic::error(
+      DiagnosticCode::InvalidOperand,
+      "Cannot perform '+' between Int and Bool.",
+      pos(file, 6, 16),
+    )
+    .with_primary_label("in this expression");
+
+    assert_eq!(
+      render(&source_map, &diagnostic),
+      "error[E0104]: Cannot perform '+' between Int and Bool.
+ --> main.rs:1:7
+  |
+1 | print(1 +
+  |       ^^^
+2 |   true);
+  |   ^^^^ in this expression
+"
+    );
+  }
+
+  #[test]
+  fn labels_in_other_files() {
+    let mut source_map = SourceMap::new();
+    let lib = source_map.add("lib.rs", "fn f() {}\n");
+    let main = source_map.add("main.rs", "fn f() {}\n");
+    let diagnostic = Diagnostic::error(
+      DiagnosticCode::DuplicateFunction,
+      "Function identifier is already taken.",
+      pos(main, 3, 4),
+    )
+    .with_label(pos(lib, 3, 4), "first declared here");
+
+    assert_eq!(
+      render(&source_map, &diagnostic),
+      "error[E0108]: Function identifier is already taken.
+ --> main.rs:1:4
+  |
+1 | fn f() {}
+  |    ^
+ ::: lib.rs:1:4
+  |
+1 | fn f() {}
+  |    - first declared here
+"
+    );
+  }
+}
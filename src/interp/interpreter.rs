@@ -0,0 +1,477 @@
+use std::{collections::HashMap, io::Write, rc::Rc};
+
+use crate::{
+  ast::SourcePos,
+  bind::bound_ast::{BoundBinaryOp, BoundExpr, BoundProgram, BoundStatement, BoundUnaryOp, Symbol},
+  diagnostic::{Diagnostic, DiagnosticCode},
+};
+
+use super::value::Value;
+
+#[derive(Debug)]
+pub enum RuntimeError {
+  /// An error caused by the program itself, e.g. a division by zero.
+  Program(Diagnostic),
+  /// Writing the output of the program failed.
+  Io(std::io::Error),
+}
+
+impl From<std::io::Error> for RuntimeError {
+  fn from(err: std::io::Error) -> Self {
+    RuntimeError::Io(err)
+  }
+}
+
+/// How the execution of a statement ended.
+enum ControlFlow {
+  Normal,
+  Break,
+  Continue,
+  Return(Value),
+}
+
+struct Function {
+  params: Vec<Symbol>,
+  body: Vec<BoundStatement>,
+}
+
+/// How many calls deep a program can go before it is stopped, rather than
+/// overflowing the stack of the interpreter itself.
+pub const MAX_CALL_DEPTH: usize = 10_000;
+
+/// The stack size of a thread able to run programs `MAX_CALL_DEPTH` calls
+/// deep, a call takes up to about 16 KiB of it in debug builds.
+pub const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+/// Evaluates bound programs directly, without going through LLVM. Its
+/// behavior is the reference for the semantics of the generated code:
+/// integer arithmetic wraps around and `&&`/`||` short-circuit.
+pub struct Interpreter<W: Write> {
+  writer: W,
+  functions: HashMap<String, Rc<Function>>,
+  /// The values of the variables of the current call frame, keyed by symbol id.
+  variables: HashMap<usize, Value>,
+  /// How many calls are currently being evaluated.
+  call_depth: usize,
+}
+
+impl<W: Write> Interpreter<W> {
+  /// Creates an interpreter that prints to `writer`.
+  pub fn new(writer: W) -> Self {
+    Self {
+      writer,
+      functions: HashMap::new(),
+      variables: HashMap::new(),
+      call_depth: 0,
+    }
+  }
+
+  /// Returns the writer the program's output was written to.
+  pub fn into_writer(self) -> W {
+    self.writer
+  }
+
+  /// Runs a program. Functions and top level variables are kept so that later
+  /// programs (e.g. REPL lines) can keep using them.
+  pub fn run(&mut self, program: &BoundProgram) -> Result<(), RuntimeError> {
+    let BoundProgram::Body { stmts } = program;
+
+    // Functions can be called before they are declared.
+    for statement in stmts {
+      if let BoundStatement::BoundFunction {
+        identifier,
+        params,
+        return_type: _,
+        body,
+        source_pos: _,
+      } = statement
+      {
+        let function = Function {
+          params: params.to_owned(),
+          body: body.to_owned(),
+        };
+        self
+          .functions
+          .insert(identifier.to_owned(), Rc::new(function));
+      }
+    }
+
+    // The binder rejects `break`, `continue` and `return` at the top level.
+    let res = self.exec_block(stmts);
+    // The output printed before an error is still shown.
+    let flushed = self.writer.flush();
+    self.call_depth = 0;
+
+    res?;
+    flushed?;
+
+    Ok(())
+  }
+
+  fn exec_block(&mut self, stmts: &[BoundStatement]) -> Result<ControlFlow, RuntimeError> {
+    for statement in stmts {
+      match self.exec_statement(statement)? {
+        ControlFlow::Normal => {}
+        control_flow => return Ok(control_flow),
+      }
+    }
+
+    Ok(ControlFlow::Normal)
+  }
+
+  fn exec_statement(&mut self, statement: &BoundStatement) -> Result<ControlFlow, RuntimeError> {
+    match statement {
+      BoundStatement::BoundExpr { expr } => {
+        self.eval(expr)?;
+      }
+      BoundStatement::BoundIf {
+        condition,
+        then_body,
+        else_body,
+      } => {
+        if self.eval(condition)?.as_bool() {
+          return self.exec_block(then_body);
+        }
+
+        if let Some(else_body) = else_body {
+          return self.exec_block(else_body);
+        }
+      }
+      BoundStatement::BoundWhile { condition, body } => {
+        while self.eval(condition)?.as_bool() {
+          match self.exec_block(body)? {
+            ControlFlow::Break => break,
+            ControlFlow::Normal | ControlFlow::Continue => {}
+            control_flow @ ControlFlow::Return(_) => return Ok(control_flow),
+          }
+        }
+      }
+      BoundStatement::BoundBreak { source_pos: _ } => return Ok(ControlFlow::Break),
+      BoundStatement::BoundContinue { source_pos: _ } => return Ok(ControlFlow::Continue),
+      // Already registered by `Self::run`.
+      BoundStatement::BoundFunction {
+        identifier: _,
+        params: _,
+        return_type: _,
+        body: _,
+        source_pos: _,
+      } => {}
+      BoundStatement::BoundReturn {
+        expr,
+        source_pos: _,
+      } => {
+        let value = match expr {
+          Some(expr) => self.eval(expr)?,
+          None => Value::Void,
+        };
+
+        return Ok(ControlFlow::Return(value));
+      }
+      BoundStatement::BoundAssignment {
+        symbol,
+        rhs,
+        source_pos: _,
+      } => {
+        let value = self.eval(rhs)?;
+        self.variables.insert(symbol.id, value);
+      }
+    }
+
+    Ok(ControlFlow::Normal)
+  }
+
+  fn eval(&mut self, expr: &BoundExpr) -> Result<Value, RuntimeError> {
+    let value = match expr {
+      BoundExpr::BoundDeclaration { symbol, rhs } => {
+        let value = self.eval(rhs)?;
+        self.variables.insert(symbol.id, value);
+        Value::Void
+      }
+      BoundExpr::BoundVariable {
+        symbol,
+        source_pos: _,
+      } => self
+        .variables
+        .get(&symbol.id)
+        .expect("The binder only allows using declared variables")
+        .to_owned(),
+      BoundExpr::Int { n, source_pos: _ } => Value::Int(*n),
+      BoundExpr::Bool { b, source_pos: _ } => Value::Bool(*b),
+      BoundExpr::String { str, source_pos: _ } => Value::String(str.to_owned()),
+      BoundExpr::BoundBinaryOp {
+        op,
+        lhs,
+        rhs,
+        bin_op_type: _,
+        source_pos,
+      } => self.eval_binary_op(*op, lhs, rhs, source_pos)?,
+      BoundExpr::BoundUnaryOp {
+        op,
+        expr,
+        source_pos: _,
+      } => {
+        let value = self.eval(expr)?;
+
+        match op {
+          BoundUnaryOp::Neg => Value::Int(value.as_int().wrapping_neg()),
+          BoundUnaryOp::Not => Value::Bool(!value.as_bool()),
+        }
+      }
+      BoundExpr::ParenthesizedExpression { expr } => self.eval(expr)?,
+      BoundExpr::BoundPrint {
+        pieces,
+        args,
+        newline,
+        source_pos: _,
+      } => self.print(pieces, args, *newline)?,
+      BoundExpr::BoundCall {
+        identifier,
+        args,
+        return_type: _,
+        source_pos,
+      } => self.call(identifier, args, source_pos)?,
+      BoundExpr::Error { source_pos: _ } => unreachable!("Programs with errors are not run"),
+    };
+
+    Ok(value)
+  }
+
+  fn print(
+    &mut self,
+    pieces: &[String],
+    args: &[BoundExpr],
+    newline: bool,
+  ) -> Result<Value, RuntimeError> {
+    let mut output = pieces[0].to_owned();
+    for (arg, piece) in args.iter().zip(pieces.iter().skip(1)) {
+      output.push_str(&self.eval(arg)?.to_string());
+      output.push_str(piece);
+    }
+
+    if newline {
+      output.push('\n');
+    }
+
+    self.writer.write_all(output.as_bytes())?;
+    Ok(Value::Void)
+  }
+
+  fn call(
+    &mut self,
+    identifier: &str,
+    args: &[BoundExpr],
+    source_pos: &SourcePos,
+  ) -> Result<Value, RuntimeError> {
+    if self.call_depth == MAX_CALL_DEPTH {
+      return Err(RuntimeError::Program(Diagnostic::error(
+        DiagnosticCode::CallDepthExceeded,
+        format!("Exceeded the maximum call depth of {}.", MAX_CALL_DEPTH),
+        source_pos.to_owned(),
+      )));
+    }
+
+    let function = Rc::clone(
+      self
+        .functions
+        .get(identifier)
+        .expect("The binder only allows calling declared functions"),
+    );
+
+    let mut frame = HashMap::new();
+    for (param, arg) in function.params.iter().zip(args) {
+      frame.insert(param.id, self.eval(arg)?);
+    }
+
+    let caller_frame = std::mem::replace(&mut self.variables, frame);
+    self.call_depth += 1;
+    let result = self.exec_block(&function.body);
+    self.call_depth -= 1;
+    self.variables = caller_frame;
+
+    match result? {
+      ControlFlow::Return(value) => Ok(value),
+      _ => Ok(Value::Void),
+    }
+  }
+
+  fn eval_binary_op(
+    &mut self,
+    op: BoundBinaryOp,
+    lhs: &BoundExpr,
+    rhs: &BoundExpr,
+    source_pos: &SourcePos,
+  ) -> Result<Value, RuntimeError> {
+    let lhs = self.eval(lhs)?;
+
+    // The right-hand side is only evaluated if it can change the result.
+    match (op, &lhs) {
+      (BoundBinaryOp::And, Value::Bool(false)) | (BoundBinaryOp::Or, Value::Bool(true)) => {
+        return Ok(lhs)
+      }
+      _ => {}
+    }
+
+    let rhs = self.eval(rhs)?;
+
+    let value = match op {
+      BoundBinaryOp::Add => Value::Int(lhs.as_int().wrapping_add(rhs.as_int())),
+      BoundBinaryOp::Sub => Value::Int(lhs.as_int().wrapping_sub(rhs.as_int())),
+      BoundBinaryOp::Mul => Value::Int(lhs.as_int().wrapping_mul(rhs.as_int())),
+      BoundBinaryOp::Div => match lhs.as_int().checked_div(rhs.as_int()) {
+        Some(n) => Value::Int(n),
+        None => {
+          let (code, msg) = match rhs.as_int() {
+            0 => (
+              DiagnosticCode::DivisionByZero,
+              "Attempted to divide by zero.",
+            ),
+            _ => (
+              DiagnosticCode::DivisionOverflow,
+              "Attempted to divide with overflow.",
+            ),
+          };
+
+          return Err(RuntimeError::Program(Diagnostic::error(
+            code,
+            msg,
+            source_pos.to_owned(),
+          )));
+        }
+      },
+      BoundBinaryOp::And | BoundBinaryOp::Or => rhs,
+      BoundBinaryOp::Eq => Value::Bool(lhs == rhs),
+      BoundBinaryOp::Ne => Value::Bool(lhs != rhs),
+      BoundBinaryOp::Lt => Value::Bool(lhs.as_int() < rhs.as_int()),
+      BoundBinaryOp::Le => Value::Bool(lhs.as_int() <= rhs.as_int()),
+      BoundBinaryOp::Gt => Value::Bool(lhs.as_int() > rhs.as_int()),
+      BoundBinaryOp::Ge => Value::Bool(lhs.as_int() >= rhs.as_int()),
+    };
+
+    Ok(value)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use crate::{ast::SourcePos, bind_sources, diagnostic::DiagnosticCode};
+
+  use super::{Interpreter, RuntimeError, STACK_SIZE};
+
+  /// Runs the source, returning its output and the error it stopped with.
+  fn run(source: &str) -> (String, Option<RuntimeError>) {
+    let program = bind_sources(&[("main", source)]).expect("Source should bind");
+
+    let mut interpreter = Interpreter::new(Vec::new());
+    let err = interpreter.run(&program).err();
+    let output = String::from_utf8(interpreter.into_writer()).expect("Output should be UTF-8");
+
+    (output, err)
+  }
+
+  /// Returns the code of the error the program stopped with and the source
+  /// it points to.
+  fn error_at(source: &str) -> (DiagnosticCode, &str) {
+    match run(source).1 {
+      Some(RuntimeError::Program(err)) => {
+        let SourcePos {
+          file: _,
+          start,
+          end,
+        } = err.primary_label.source_pos;
+        (err.code, &source[start..end])
+      }
+      err => panic!("Expected a program error, found {:?}", err),
+    }
+  }
+
+  #[test]
+  fn arithmetic_wraps_around() {
+    let (output, err) = run(
+      "let max = 2147483647;
+      let min = -max - 1;
+      println(\"{} {} {} {}\", max + 1, min - 1, max * 2, -min);",
+    );
+
+    assert!(err.is_none());
+    assert_eq!(output, "-2147483648 2147483647 -2 -2147483648\n");
+  }
+
+  #[test]
+  fn logical_operators_short_circuit() {
+    let (output, err) = run(
+      "fn f(b: bool) -> bool { print(\"f \"); return b; }
+      let x = false && f(true);
+      let y = true || f(false);
+      let z = true && f(false);
+      println(\"{} {} {}\", x, y, z);",
+    );
+
+    assert!(err.is_none());
+    assert_eq!(output, "f false true false\n");
+  }
+
+  #[test]
+  fn division_errors_point_at_the_operator() {
+    assert_eq!(
+      error_at("let zero = 0; let x = 1 / zero;"),
+      (DiagnosticCode::DivisionByZero, "/")
+    );
+    assert_eq!(
+      error_at("let min = -2147483647 - 1; let x = min / -1;"),
+      (DiagnosticCode::DivisionOverflow, "/")
+    );
+  }
+
+  #[test]
+  fn output_before_an_error_is_written() {
+    let (output, err) = run("println(\"before\"); let zero = 0; println(\"{}\", 1 / zero);");
+
+    assert!(err.is_some());
+    assert_eq!(output, "before\n");
+  }
+
+  #[test]
+  fn loop_control_flow() {
+    let (output, err) = run(
+      "fn first(limit: int) -> int {
+        let mut i = 0;
+        while true {
+          i += 1;
+          if i == limit { return i; }
+        }
+        return 0;
+      }
+      let mut i = 0;
+      while i < 10 {
+        i += 1;
+        if i == 2 { continue; }
+        if i == 5 { break; }
+        print(\"{} \", i);
+      }
+      println(\"{}\", first(3));",
+    );
+
+    assert!(err.is_none());
+    assert_eq!(output, "1 3 4 3\n");
+  }
+
+  #[test]
+  fn deep_recursion_is_stopped() {
+    let source = "fn sum(n: int) -> int { if n == 0 { return 0; } return n + sum(n - 1); }
+      println(\"{}\", sum(20000));";
+
+    // Like the CLI, runs on a thread with a stack deep enough for the limit.
+    let (code, call) = std::thread::Builder::new()
+      .stack_size(STACK_SIZE)
+      .spawn(move || {
+        let (code, call) = error_at(source);
+        (code, call.to_owned())
+      })
+      .expect("Thread should spawn")
+      .join()
+      .expect("Interpreter should not overflow its stack");
+
+    assert_eq!(code, DiagnosticCode::CallDepthExceeded);
+    assert_eq!(call, "sum(n - 1)");
+  }
+}
@@ -0,0 +1,42 @@
+use std::fmt;
+
+/// The result of evaluating a bound expression.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Value {
+  Int(i32),
+  Bool(bool),
+  String(String),
+  /// The result of expressions that do not produce a value, e.g. calls to
+  /// functions without a return type.
+  Void,
+}
+
+impl Value {
+  /// Returns the contained integer. The binder guarantees the type of every
+  /// value, so anything else is a bug.
+  pub fn as_int(&self) -> i32 {
+    match self {
+      Value::Int(n) => *n,
+      _ => panic!("Expected Int, found {:?}", self),
+    }
+  }
+
+  /// Returns the contained boolean, see `Self::as_int`.
+  pub fn as_bool(&self) -> bool {
+    match self {
+      Value::Bool(b) => *b,
+      _ => panic!("Expected Bool, found {:?}", self),
+    }
+  }
+}
+
+impl fmt::Display for Value {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Value::Int(n) => write!(f, "{}", n),
+      Value::Bool(b) => write!(f, "{}", b),
+      Value::String(str) => write!(f, "{}", str),
+      Value::Void => Ok(()),
+    }
+  }
+}
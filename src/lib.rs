@@ -3,54 +3,130 @@
 pub mod ast;
 pub mod bind;
 pub mod code_gen;
+pub mod diagnostic;
+pub mod interp;
+pub mod module_loader;
+pub mod source_map;
 
-use bind::bound_ast::{BoundExpr, BoundProgram, BoundStatement, TypeError};
+use ast::Program;
+use bind::bound_ast::{BoundExpr, BoundProgram, BoundStatement};
+use diagnostic::{
+  render::{ColorChoice, Renderer},
+  Diagnostic,
+};
+use lalrpop_util::{lexer::Token, ParseError};
 use parser::ProgramParser;
+use source_map::{FileId, SourceMap};
 
-use crate::bind::bound_ast::BoundBinaryOp;
+use crate::bind::bound_ast::{BoundBinaryOp, BoundUnaryOp};
 
-#[macro_use]
-extern crate lalrpop_util;
+#[allow(clippy::all, clippy::unwrap_used)]
+pub mod parser {
+  include!(concat!(env!("OUT_DIR"), "/parser.rs"));
 
-lalrpop_mod!(pub parser);
+  /// Splits the input into the tokens seen by the parser, along with their
+  /// start and end positions.
+  pub fn tokenize(
+    input: &str,
+  ) -> Result<
+    Vec<(usize, lalrpop_util::lexer::Token<'_>, usize)>,
+    lalrpop_util::ParseError<usize, lalrpop_util::lexer::Token<'_>, &'static str>,
+  > {
+    __intern_token::new_builder().matcher(input).collect()
+  }
 
-const RED: &str = "\x1b[31m";
-const RESET: &str = "\x1b[0m";
-const BOLD: &str = "\x1b[1m";
-const RBOLD: &str = "\x1b[22m";
-const GRAY: &str = "\x1b[38;5;8m";
+  /// Whether the token is one the lexer produces for input no other token
+  /// matches, an unknown character or a malformed string.
+  pub fn is_unknown(token: &lalrpop_util::lexer::Token<'_>) -> bool {
+    ["$", "\"'"]
+      .iter()
+      .any(|sample| match tokenize(sample).as_deref() {
+        Ok([(_, unknown, _)]) => unknown.0 == token.0,
+        _ => false,
+      })
+  }
+}
 
 pub fn create_parser() -> ProgramParser {
   parser::ProgramParser::new()
 }
 
-fn print_expr(expr: BoundExpr, indent: &str, is_last: bool) {
-  let marker = if is_last {
-    "└───"
-  } else {
-    "├───"
-  };
+/// Syntax errors, in the order they were encountered.
+pub type SyntaxErrors<'input> = Vec<ParseError<usize, Token<'input>, &'static str>>;
+
+/// Parses the input, the contents of `file`, continuing after syntax errors
+/// where possible. The program is `None` if the parser could not recover from
+/// an error.
+pub fn parse<'input>(
+  parser: &ProgramParser,
+  file: FileId,
+  input: &'input str,
+) -> (Option<Program>, SyntaxErrors<'input>) {
+  let mut recovered = Vec::new();
+  let result = parser.parse(file, &mut recovered, input);
+  let mut errors = recovered
+    .into_iter()
+    .map(|recovery| recovery.error)
+    .collect::<SyntaxErrors>();
+
+  match result {
+    Ok(program) => (Some(program), errors),
+    Err(err) => {
+      errors.push(err);
+      (None, errors)
+    }
+  }
+}
+
+/// Parses and binds the `(name, source)` modules, the first one being the
+/// entry. Panics if one of them has a syntax error.
+#[cfg(test)]
+pub(crate) fn bind_sources(
+  sources: &[(&str, &str)],
+) -> Result<BoundProgram, bind::bound_ast::ProgramError> {
+  let parser = create_parser();
+  let mut source_map = SourceMap::new();
+  let modules = sources
+    .iter()
+    .map(|(name, source)| {
+      let (program, errors) = parse(&parser, source_map.add(*name, *source), source);
+      match program {
+        Some(program) if errors.is_empty() => ast::Module {
+          name: name.to_string(),
+          program,
+        },
+        _ => panic!("Module {} should parse", name),
+      }
+    })
+    .collect::<Vec<ast::Module>>();
+
+  bind::binder::Binder::new().bind_modules(&modules)
+}
 
-  print!("{}", indent);
-  print!("{}", marker);
+fn print_expr(expr: BoundExpr, indent: &str, is_last: bool, color: bool) {
+  print!("{}{}", indent, marker(is_last));
 
   match expr {
     BoundExpr::Int {
       n: _,
-      // source_pos: _,
-    } => print!("{}", green_text("Int")),
+      source_pos: _,
+    } => print!("{}", green_text("Int", color)),
     BoundExpr::Bool {
       b: _,
-      // source_pos: _,
-    } => print!("{}", green_text("Bool")),
-    BoundExpr::String { str: _ } => print!("{}", green_text("String")),
+      source_pos: _,
+    } => print!("{}", green_text("Bool", color)),
+    BoundExpr::String {
+      str: _,
+      source_pos: _,
+    } => print!("{}", green_text("String", color)),
     BoundExpr::BoundBinaryOp {
       op,
       lhs: _,
       rhs: _,
       bin_op_type: _,
+      source_pos: _,
     } => {
-      print!("{}", green_text("BinaryOp"));
+      print!("{}", green_text("BinaryOp", color));
       match op {
         BoundBinaryOp::Add => print!(" +"),
         BoundBinaryOp::Sub => print!(" -"),
@@ -58,109 +134,280 @@ fn print_expr(expr: BoundExpr, indent: &str, is_last: bool) {
         BoundBinaryOp::Div => print!(" /"),
         BoundBinaryOp::And => print!(" &&"),
         BoundBinaryOp::Or => print!(" ||"),
+        BoundBinaryOp::Eq => print!(" =="),
+        BoundBinaryOp::Ne => print!(" !="),
+        BoundBinaryOp::Lt => print!(" <"),
+        BoundBinaryOp::Le => print!(" <="),
+        BoundBinaryOp::Gt => print!(" >"),
+        BoundBinaryOp::Ge => print!(" >="),
+      }
+    }
+    BoundExpr::BoundUnaryOp {
+      op,
+      expr: _,
+      source_pos: _,
+    } => {
+      print!("{}", green_text("UnaryOp", color));
+      match op {
+        BoundUnaryOp::Neg => print!(" -"),
+        BoundUnaryOp::Not => print!(" !"),
       }
     }
     BoundExpr::ParenthesizedExpression { expr: _ } => {
-      print!("{}", green_text("ParenthesizedExpression"))
+      print!("{}", green_text("ParenthesizedExpression", color))
     }
     BoundExpr::BoundPrint {
-      expr_type: _,
-      expr: _,
-    } => print!("{}", green_text("Print:")),
-    BoundExpr::BoundDeclaration {
+      pieces: _,
+      args: _,
+      newline,
+      source_pos: _,
+    } => match newline {
+      true => print!("{}", green_text("Println:", color)),
+      false => print!("{}", green_text("Print:", color)),
+    },
+    BoundExpr::BoundDeclaration { symbol: _, rhs: _ } => {
+      print!("{}", green_text("BoundDeclaration", color))
+    }
+    BoundExpr::BoundVariable {
+      symbol: _,
+      source_pos: _,
+    } => print!("{}", green_text("Variable", color)),
+    BoundExpr::BoundCall {
       identifier: _,
-      value_type: _,
-      rhs: _,
-    } => print!("{}", green_text("BoundDeclaration")),
+      args: _,
+      return_type: _,
+      source_pos: _,
+    } => print!("{}", green_text("Call", color)),
+    BoundExpr::Error { source_pos: _ } => print!("{}", green_text("Error", color)),
   }
 
-  let indent = indent.to_owned() + if is_last { "    " } else { "│   " };
+  let indent = child_indent(indent, is_last);
 
   match expr {
-    BoundExpr::Int { n } => println!("{}", red_text(&n.to_string())),
-    BoundExpr::Bool { b } => println!("{}", red_text(&b.to_string())),
-    BoundExpr::String { str } => println!("{}", red_text(&str)),
+    BoundExpr::Int { n, source_pos: _ } => println!("{}", red_text(&n.to_string(), color)),
+    BoundExpr::Bool { b, source_pos: _ } => println!("{}", red_text(&b.to_string(), color)),
+    BoundExpr::String { str, source_pos: _ } => println!("{}", red_text(&str, color)),
     BoundExpr::BoundBinaryOp {
       op: _,
       lhs,
       rhs,
       bin_op_type: _,
+      source_pos: _,
+    } => {
+      println!();
+      print_expr(*lhs, &indent, false, color);
+      print_expr(*rhs, &indent, true, color);
+    }
+    BoundExpr::BoundUnaryOp {
+      op: _,
+      expr,
+      source_pos: _,
     } => {
       println!();
-      print_expr(*lhs, &indent, false);
-      print_expr(*rhs, &indent, true);
+      print_expr(*expr, &indent, true, color);
     }
     BoundExpr::ParenthesizedExpression { expr } => {
       println!();
-      print_expr(*expr, &indent, true);
+      print_expr(*expr, &indent, true, color);
     }
-    BoundExpr::BoundPrint { expr_type: _, expr } => {
+    BoundExpr::BoundPrint {
+      pieces,
+      args,
+      newline: _,
+      source_pos: _,
+    } => {
+      println!("{}", red_text(&format!("{:?}", pieces.join("{}")), color));
+      for (i, arg) in args.iter().enumerate() {
+        print_expr(arg.to_owned(), &indent, i == args.len() - 1, color);
+      }
+    }
+    BoundExpr::BoundDeclaration { symbol, rhs } => {
       println!();
-      print_expr(*expr, "", is_last);
+      print!("{}", &indent[0..]);
+      println!("{}", red_text(&symbol.name, color));
+      print_expr(*rhs, &indent, is_last, color)
     }
-    BoundExpr::BoundDeclaration {
+    BoundExpr::BoundVariable {
+      symbol,
+      source_pos: _,
+    } => println!("{}", red_text(&symbol.name, color)),
+    BoundExpr::BoundCall {
       identifier,
-      value_type: _,
+      args,
+      return_type: _,
+      source_pos: _,
+    } => {
+      println!("{}", red_text(&identifier, color));
+      for (i, arg) in args.iter().enumerate() {
+        print_expr(arg.to_owned(), &indent, i == args.len() - 1, color);
+      }
+    }
+    BoundExpr::Error { source_pos: _ } => println!(),
+  }
+}
+
+fn print_statement(statement: &BoundStatement, indent: &str, is_last: bool, color: bool) {
+  match statement {
+    BoundStatement::BoundExpr { expr } => print_expr(expr.to_owned(), indent, is_last, color),
+    BoundStatement::BoundIf {
+      condition,
+      then_body,
+      else_body,
+    } => {
+      println!("{}{}{}", indent, marker(is_last), green_text("If", color));
+
+      let indent = child_indent(indent, is_last);
+      print_expr(condition.to_owned(), &indent, false, color);
+      print_block("Then", then_body, &indent, else_body.is_none(), color);
+
+      if let Some(else_body) = else_body {
+        print_block("Else", else_body, &indent, true, color);
+      }
+    }
+    BoundStatement::BoundWhile { condition, body } => {
+      println!(
+        "{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("While", color)
+      );
+
+      let indent = child_indent(indent, is_last);
+      print_expr(condition.to_owned(), &indent, false, color);
+      print_block("Body", body, &indent, true, color);
+    }
+    BoundStatement::BoundBreak { source_pos: _ } => {
+      println!(
+        "{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("Break", color)
+      );
+    }
+    BoundStatement::BoundContinue { source_pos: _ } => {
+      println!(
+        "{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("Continue", color)
+      );
+    }
+    BoundStatement::BoundFunction {
+      identifier,
+      params,
+      return_type,
+      body,
+      source_pos: _,
+    } => {
+      let params = params
+        .iter()
+        .map(|param| format!("{}: {:?}", param.name, param.value_type))
+        .collect::<Vec<String>>()
+        .join(", ");
+      println!(
+        "{}{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("Function", color),
+        red_text(
+          &format!("{}({}) -> {:?}", identifier, params, return_type),
+          color
+        )
+      );
+
+      let indent = child_indent(indent, is_last);
+      print_block("Body", body, &indent, true, color);
+    }
+    BoundStatement::BoundReturn {
+      expr,
+      source_pos: _,
+    } => {
+      println!(
+        "{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("Return", color)
+      );
+
+      if let Some(expr) = expr {
+        let indent = child_indent(indent, is_last);
+        print_expr(expr.to_owned(), &indent, true, color);
+      }
+    }
+    BoundStatement::BoundAssignment {
+      symbol,
       rhs,
+      source_pos: _,
     } => {
-      println!();
-      print!("{}", &indent[0..]);
-      println!("{}", red_text(&identifier));
-      print_expr(*rhs, &indent, is_last)
+      println!(
+        "{}{}{}{}",
+        indent,
+        marker(is_last),
+        green_text("Assignment", color),
+        red_text(&symbol.name, color)
+      );
+
+      let indent = child_indent(indent, is_last);
+      print_expr(rhs.to_owned(), &indent, true, color);
     }
   }
 }
 
+fn print_block(name: &str, stmts: &[BoundStatement], indent: &str, is_last: bool, color: bool) {
+  println!("{}{}{}", indent, marker(is_last), green_text(name, color));
+
+  let indent = child_indent(indent, is_last);
+  for (i, statement) in stmts.iter().enumerate() {
+    print_statement(statement, &indent, i == stmts.len() - 1, color);
+  }
+}
+
+/// The marker in front of a node, the last child of its parent closes the branch.
+fn marker(is_last: bool) -> &'static str {
+  match is_last {
+    true => "└───",
+    false => "├───",
+  }
+}
+
+/// The indent of the children of a node indented by `indent`.
+fn child_indent(indent: &str, is_last: bool) -> String {
+  match is_last {
+    true => format!("{}    ", indent),
+    false => format!("{}│   ", indent),
+  }
+}
+
 /// Prints a tree like view of the passed expression.
-pub fn print_program(prog: &BoundProgram) {
+pub fn print_program(prog: &BoundProgram, color: ColorChoice) {
+  let color = color.enabled(&std::io::stdout());
+
   match prog {
     BoundProgram::Body { stmts } => {
       for statement in stmts {
-        match statement {
-          BoundStatement::BoundExpr { expr } => print_expr(expr.to_owned(), "", true),
-        }
+        print_statement(statement, "", true, color);
       }
     }
   }
 }
 
 // https://gist.github.com/fnky/458719343aabd01cfb17a3a4f7296797
-fn red_text(str: &str) -> String {
-  format!("\x1b[31m({})\x1b[0m", str)
+fn red_text(str: &str, color: bool) -> String {
+  match color {
+    true => format!("\x1b[31m({})\x1b[0m", str),
+    false => format!("({})", str),
+  }
 }
 
-fn green_text(str: &str) -> String {
-  format!("\x1b[32m{}\x1b[0m", str.to_owned())
+fn green_text(str: &str, color: bool) -> String {
+  match color {
+    true => format!("\x1b[32m{}\x1b[0m", str),
+    false => str.to_owned(),
+  }
 }
 
-pub fn print_error(str: &str, err: TypeError) {
-  // new err
-  let start = err.source_pos.start;
-  let end = err.source_pos.end;
-
-  let lines = str.split('\n');
-  let mut count = 0;
-  let mut line_count = 1;
-
-  for line in lines {
-    if count + line.len() >= start {
-      // Print error message
-      println!(
-        "\n{RED}ERROR{RESET}: {BOLD}{}{RBOLD} {GRAY}Ln {}{RESET}",
-        err.msg, line_count
-      );
-      // Print erroneous line_count
-      println!("{GRAY}{}{RESET}", line);
-
-      // Print error indicator
-      let before = " ".repeat(start - count);
-      let err = "^".repeat((end - count) - (start - count));
-      println!("{}{RED}{}{RESET}", before, err);
-      break;
-    } else {
-      // Add line length + the \n we removed earlier
-      count += line.len() + 1;
-      line_count += 1;
-    }
-  }
+/// Prints the diagnostic to stderr as `path:line:col` followed by the source
+/// it points at.
+pub fn print_error(renderer: &Renderer, source_map: &SourceMap, err: &Diagnostic) {
+  eprintln!("{}", renderer.render(source_map, err));
 }
@@ -2,43 +2,285 @@
 #![allow(dead_code)]
 #![warn(clippy::unwrap_used)]
 
+mod cli;
+
+use cli::{Args, Command, EmitKind};
 use lalrpop_util::{lexer::Token, ParseError};
 use rust_sharp::{
-  ast::SourcePos,
-  bind::{binder::Binder, bound_ast::TypeError},
-  code_gen::llvm_module::LLVMProgramBuilder,
+  bind::{binder::Binder, bound_ast::BoundProgram},
+  code_gen::{
+    driver::{build_executable, Toolchain},
+    llvm_module::LLVMProgramBuilder,
+  },
   create_parser,
-  parser::ProgramParser,
+  diagnostic::{
+    render::{ColorChoice, Renderer},
+    Diagnostic,
+  },
+  interp::interpreter::{Interpreter, RuntimeError, STACK_SIZE},
+  module_loader::load_modules,
+  parse,
+  parser::{tokenize, ProgramParser},
   print_error, print_program,
+  source_map::{FileId, SourceMap},
+};
+use std::{
+  io::Write,
+  path::{Path, PathBuf},
+  process::ExitCode,
 };
-use std::io::Write;
 
-fn main() {
-  // code_gen::tmp::main();
+/// The name errors in REPL input are reported under.
+const REPL_FILE_NAME: &str = "<repl>";
+
+fn main() -> ExitCode {
+  let Args { command, color } = match cli::parse_args(std::env::args().skip(1)) {
+    Ok(args) => args,
+    Err(msg) => {
+      eprintln!("{}\n\n{}", msg, cli::USAGE);
+      return ExitCode::from(2);
+    }
+  };
+
+  // Interpreted calls recurse on the stack of the thread running them.
+  let success = std::thread::Builder::new()
+    .stack_size(STACK_SIZE)
+    .spawn(move || run_command(command, color))
+    .expect("Unable to spawn the main thread")
+    .join()
+    .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+
+  match success {
+    true => ExitCode::SUCCESS,
+    false => ExitCode::FAILURE,
+  }
+}
+
+/// Runs the command, returning whether it succeeded.
+fn run_command(command: Command, color: ColorChoice) -> bool {
   let parser = create_parser();
+  let renderer = Renderer::new(color);
 
-  // https://stackoverflow.com/a/34837038/12756474
-  print!("{esc}[2J{esc}[1;1H", esc = 27 as char);
+  match command {
+    Command::Check { file } => {
+      let mut source_map = SourceMap::new();
+      read_file(&mut source_map, &file)
+        .and_then(|file| {
+          compile(
+            &parser,
+            &renderer,
+            &mut source_map,
+            file,
+            &mut Binder::new(),
+          )
+        })
+        .is_some()
+    }
+    Command::Build {
+      file,
+      output,
+      toolchain,
+    } => build(&parser, &renderer, &file, output, &toolchain),
+    Command::Run { file } => run(&parser, &renderer, &file),
+    Command::Emit { kind, file } => emit(&parser, &renderer, color, kind, &file),
+    Command::Repl => {
+      read_repl(&parser, &renderer);
+      true
+    }
+    Command::Help => {
+      println!("{}", cli::USAGE);
+      true
+    }
+  }
+}
 
-  read_test(&parser);
-  // read_repl(&parser);
+/// Adds the file to the source map, returning its id.
+fn read_file(source_map: &mut SourceMap, file: &str) -> Option<FileId> {
+  match std::fs::read_to_string(file) {
+    Ok(contents) => Some(source_map.add(file, contents)),
+    Err(err) => {
+      eprintln!("Unable to read {}: {}", file, err);
+      None
+    }
+  }
 }
 
-/// Reads a hardcoded test file from the `samples/` folder.
-fn read_test(parser: &ProgramParser) {
-  let file_path = "samples/test.rsharp";
-  let contents =
-    std::fs::read_to_string(file_path).unwrap_or_else(|_| panic!("Unable to read {}", file_path));
+/// Parses and binds the file along with the modules it imports, printing any
+/// errors along the way.
+fn compile(
+  parser: &ProgramParser,
+  renderer: &Renderer,
+  source_map: &mut SourceMap,
+  file: FileId,
+  binder: &mut Binder,
+) -> Option<BoundProgram> {
+  let (modules, syntax_errors) = load_modules(parser, source_map, file);
+  for err in &syntax_errors {
+    print_error(renderer, source_map, err);
+  }
 
-  let mut binder = Binder::new();
-  let mut llvm_builder = LLVMProgramBuilder::new();
-  parse_input(parser, &contents, &mut binder, &mut llvm_builder);
+  // The rest of the program is still checked if the parser recovered.
+  match binder.bind_modules(&modules?) {
+    Ok(_) if !syntax_errors.is_empty() => None,
+    Ok(bound_program) => Some(bound_program),
+    Err(errs) => {
+      for err in errs.expr_errors {
+        print_error(renderer, source_map, &err)
+      }
+
+      None
+    }
+  }
+}
+
+/// Compiles the file to an executable, placed next to it unless `output` is given.
+fn build(
+  parser: &ProgramParser,
+  renderer: &Renderer,
+  file: &str,
+  output: Option<String>,
+  toolchain: &Toolchain,
+) -> bool {
+  let mut source_map = SourceMap::new();
+  let Some(file_id) = read_file(&mut source_map, file) else {
+    return false;
+  };
+  let Some(program) = compile(
+    parser,
+    renderer,
+    &mut source_map,
+    file_id,
+    &mut Binder::new(),
+  ) else {
+    return false;
+  };
+
+  let module = match LLVMProgramBuilder::new().generate_llvm(program) {
+    Ok(module) => module,
+    Err(err) => {
+      eprintln!("Code generation failed: {}", err);
+      return false;
+    }
+  };
+
+  let output = match output {
+    Some(output) => PathBuf::from(output),
+    // Without an extension the executable would overwrite the source file.
+    None => match Path::new(file).extension() {
+      Some(_) => Path::new(file).with_extension(""),
+      None => Path::new(file).with_extension("out"),
+    },
+  };
+
+  match build_executable(&module, &output, toolchain) {
+    Ok(_) => true,
+    Err(err) => {
+      eprintln!("{}", err);
+      false
+    }
+  }
+}
+
+/// Runs the file using the interpreter.
+fn run(parser: &ProgramParser, renderer: &Renderer, file: &str) -> bool {
+  let mut source_map = SourceMap::new();
+  let Some(file_id) = read_file(&mut source_map, file) else {
+    return false;
+  };
+  let Some(program) = compile(
+    parser,
+    renderer,
+    &mut source_map,
+    file_id,
+    &mut Binder::new(),
+  ) else {
+    return false;
+  };
+
+  let mut interpreter = Interpreter::new(std::io::stdout().lock());
+  match interpreter.run(&program) {
+    Ok(_) => true,
+    Err(err) => {
+      handle_runtime_error(renderer, &source_map, err);
+      false
+    }
+  }
+}
+
+/// Prints one of the intermediate representations of the file, `color` applies
+/// to the bound tree.
+fn emit(
+  parser: &ProgramParser,
+  renderer: &Renderer,
+  color: ColorChoice,
+  kind: EmitKind,
+  file: &str,
+) -> bool {
+  let mut source_map = SourceMap::new();
+  let Some(file) = read_file(&mut source_map, file) else {
+    return false;
+  };
+  let input = source_map.get(file).source();
+
+  match kind {
+    EmitKind::Tokens => match tokenize(input) {
+      Ok(tokens) => {
+        for (start, token, end) in tokens {
+          println!("{}..{} {:?}", start, end, token.1);
+        }
+
+        true
+      }
+      Err(err) => {
+        handle_parse_error(parser, renderer, &source_map, file, &err);
+        false
+      }
+    },
+    EmitKind::Ast => {
+      let (program, syntax_errors) = parse(parser, file, input);
+      for err in &syntax_errors {
+        handle_parse_error(parser, renderer, &source_map, file, err);
+      }
+
+      if let Some(program) = program {
+        println!("{:#?}", program);
+      }
+
+      syntax_errors.is_empty()
+    }
+    EmitKind::Bound => match compile(parser, renderer, &mut source_map, file, &mut Binder::new()) {
+      Some(program) => {
+        print_program(&program, color);
+        true
+      }
+      None => false,
+    },
+    EmitKind::Llvm => {
+      let Some(program) = compile(parser, renderer, &mut source_map, file, &mut Binder::new())
+      else {
+        return false;
+      };
+
+      match LLVMProgramBuilder::new().generate_llvm(program) {
+        Ok(module) => {
+          println!("{}", module);
+          true
+        }
+        Err(err) => {
+          eprintln!("Code generation failed: {}", err);
+          false
+        }
+      }
+    }
+  }
 }
 
 /// Runs a Read Eval Print Loop. Enter an empty string to exit.
-fn read_repl(parser: &ProgramParser) {
+fn read_repl(parser: &ProgramParser, renderer: &Renderer) {
   let mut binder = Binder::new();
-  let mut llvm_builder = LLVMProgramBuilder::new();
+  let mut interpreter = Interpreter::new(std::io::stdout());
+  // Every line is added as a file of its own, errors can point into earlier ones.
+  let mut source_map = SourceMap::new();
 
   loop {
     print!("> ");
@@ -54,80 +296,127 @@ fn read_repl(parser: &ProgramParser) {
       break;
     }
 
-    parse_input(parser, &line, &mut binder, &mut llvm_builder);
+    let file = source_map.add(REPL_FILE_NAME, line);
+    eval_repl_line(
+      parser,
+      renderer,
+      &mut source_map,
+      file,
+      &mut binder,
+      &mut interpreter,
+    );
   }
 }
 
-#[allow(clippy::unwrap_used)]
-fn parse_input(
+/// Compiles and runs a line of REPL input. Whatever the line declared is
+/// forgotten if it fails to compile or run, as its declarations may never have
+/// been executed.
+fn eval_repl_line<W: Write>(
   parser: &ProgramParser,
-  input: &str,
+  renderer: &Renderer,
+  source_map: &mut SourceMap,
+  file: FileId,
   binder: &mut Binder,
-  llvm_builder: &mut LLVMProgramBuilder,
-) {
-  // Parse
-  let parsed_input = &parser.parse(input);
-  if let Err(err) = parsed_input {
-    handle_parse_error(input, err);
-    return;
+  interpreter: &mut Interpreter<W>,
+) -> bool {
+  let snapshot = binder.clone();
+
+  let success = match compile(parser, renderer, source_map, file, binder) {
+    Some(program) => match interpreter.run(&program) {
+      Ok(_) => true,
+      Err(err) => {
+        handle_runtime_error(renderer, source_map, err);
+        false
+      }
+    },
+    None => false,
+  };
+
+  if !success {
+    *binder = snapshot;
   }
 
-  // Bind
-  let bind_result = binder.bind(parsed_input.as_ref().unwrap());
+  success
+}
 
-  if let Err(errs) = bind_result {
-    for err in errs.expr_errors {
-      print_error(input, err.to_owned())
-    }
+fn handle_runtime_error(renderer: &Renderer, source_map: &SourceMap, err: RuntimeError) {
+  match err {
+    RuntimeError::Program(err) => print_error(renderer, source_map, &err),
+    RuntimeError::Io(err) => eprintln!("Unable to write output: {}", err),
+  }
+}
+
+fn handle_parse_error(
+  parser: &ProgramParser,
+  renderer: &Renderer,
+  source_map: &SourceMap,
+  file: FileId,
+  e: &ParseError<usize, Token<'_>, &str>,
+) {
+  let err = Diagnostic::from_parse_error(parser, source_map.get(file), e);
+  print_error(renderer, source_map, &err)
+}
+
+#[cfg(test)]
+mod tests {
+  use rust_sharp::{
+    bind::binder::Binder,
+    create_parser,
+    diagnostic::render::{ColorChoice, Renderer},
+    interp::interpreter::Interpreter,
+    source_map::SourceMap,
+  };
+
+  use super::{eval_repl_line, REPL_FILE_NAME};
+
+  /// Runs the lines as a REPL session, returning which of them succeeded and
+  /// the output.
+  fn repl(lines: &[&str]) -> (Vec<bool>, String) {
+    let parser = create_parser();
+    let renderer = Renderer::new(ColorChoice::Never);
+    let mut source_map = SourceMap::new();
+    let mut binder = Binder::new();
+    let mut interpreter = Interpreter::new(Vec::new());
+
+    let results = lines
+      .iter()
+      .map(|line| {
+        let file = source_map.add(REPL_FILE_NAME, *line);
+        eval_repl_line(
+          &parser,
+          &renderer,
+          &mut source_map,
+          file,
+          &mut binder,
+          &mut interpreter,
+        )
+      })
+      .collect();
+    let output = String::from_utf8(interpreter.into_writer()).expect("Output should be UTF-8");
 
-    return;
+    (results, output)
   }
 
-  let bind_result = bind_result.unwrap();
-  print_program(&bind_result);
+  #[test]
+  fn failed_lines_are_forgotten() {
+    let (results, output) = repl(&[
+      "let y = 1; let z = nope;",
+      "print(y);",
+      "print(z);",
+      "fn f() -> int { return 1; } let w = nope;",
+      "print(f());",
+      "let y = 2; println(\"{}\", y);",
+    ]);
 
-  // Compile
-  llvm_builder.generate_llvm(bind_result);
-}
+    assert_eq!(results, vec![false, false, false, false, false, true]);
+    assert_eq!(output, "2\n");
+  }
 
-fn handle_parse_error(line: &str, e: &ParseError<usize, Token<'_>, &str>) {
-  match e {
-    lalrpop_util::ParseError::UnrecognizedEOF {
-      location,
-      expected: _,
-    } => {
-      let msg = "Bad End Of File.".to_string();
-      let err = TypeError {
-        msg,
-        source_pos: SourcePos {
-          start: *location,
-          end: location + 1,
-        },
-      };
-      print_error(line, err)
-    }
-    lalrpop_util::ParseError::UnrecognizedToken { token, expected: _ } => {
-      let msg = "Unrecognized Token.".to_string();
-      let err = TypeError {
-        msg,
-        source_pos: SourcePos {
-          start: token.0,
-          end: token.2.to_owned(),
-        },
-      };
-      print_error(line, err)
-    }
-    ParseError::InvalidToken { location } => {
-      let msg = "Invalid Token.".to_string();
-      let err = TypeError {
-        msg,
-        source_pos: SourcePos {
-          start: location - 1,
-          end: location.to_owned(),
-        },
-      };
-      print_error(line, err)
-    }
-    _ => todo!(),
+  #[test]
+  fn lines_failing_at_runtime_are_forgotten() {
+    let (results, output) = repl(&["let x = 1;", "let y = x / 0;", "print(y);", "print(x);"]);
+
+    assert_eq!(results, vec![true, false, false, true]);
+    assert_eq!(output, "1");
   }
 }
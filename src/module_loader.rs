@@ -0,0 +1,96 @@
+// finds, reads and parses the modules imported by a program
+
+use std::{collections::HashSet, io::ErrorKind, path::Path};
+
+use crate::{
+  ast::Module,
+  diagnostic::{Diagnostic, DiagnosticCode},
+  parse,
+  parser::ProgramParser,
+  source_map::{FileId, SourceMap},
+};
+
+/// The extension of the files modules are loaded from.
+pub const MODULE_EXTENSION: &str = "rsharp";
+
+/// Parses the entry file along with every module it imports, directly or not,
+/// adding the files of the imported modules to `source_map`. Modules are
+/// looked up next to the file importing them, `import math;` loads
+/// `math.rsharp`.
+///
+/// The entry module comes first. Import cycles are left to the binder to
+/// report. The modules are `None` if one of them could not be read or parsed,
+/// the errors encountered are returned either way.
+pub fn load_modules(
+  parser: &ProgramParser,
+  source_map: &mut SourceMap,
+  entry: FileId,
+) -> (Option<Vec<Module>>, Vec<Diagnostic>) {
+  let entry_name = module_name(source_map.get(entry).name());
+  let mut seen = HashSet::from([entry_name.to_owned()]);
+  let mut pending = vec![(entry_name, entry)];
+  let mut modules = Vec::<Module>::new();
+  let mut errors = Vec::<Diagnostic>::new();
+  let mut failed = false;
+
+  while let Some((name, file)) = pending.pop() {
+    let source_file = source_map.get(file);
+    let (program, syntax_errors) = parse(parser, file, source_file.source());
+    errors.extend(
+      syntax_errors
+        .iter()
+        .map(|err| Diagnostic::from_parse_error(parser, source_file, err)),
+    );
+
+    let Some(program) = program else {
+      failed = true;
+      continue;
+    };
+
+    let dir = Path::new(source_file.name())
+      .parent()
+      .unwrap_or(Path::new(""))
+      .to_owned();
+    for import in program.imports() {
+      if !seen.insert(import.text.to_owned()) {
+        continue;
+      }
+
+      let path = dir.join(format!("{}.{}", import.text, MODULE_EXTENSION));
+      match std::fs::read_to_string(&path) {
+        Ok(source) => {
+          let file = source_map.add(path.to_string_lossy(), source);
+          pending.push((import.text.to_owned(), file));
+        }
+        Err(err) => {
+          let msg = match err.kind() {
+            ErrorKind::NotFound => format!("Cannot find module '{}'.", import.text),
+            _ => format!("Unable to read module '{}': {}.", import.text, err),
+          };
+
+          errors.push(
+            Diagnostic::error(DiagnosticCode::UnknownModule, msg, import.source_pos)
+              .with_help(format!("expected it at '{}'", path.display())),
+          );
+          failed = true;
+        }
+      }
+    }
+
+    modules.push(Module { name, program });
+  }
+
+  match failed {
+    true => (None, errors),
+    false => (Some(modules), errors),
+  }
+}
+
+/// Returns the name a file is imported by, `math.rsharp` is imported as `math`.
+fn module_name(file_name: &str) -> String {
+  Path::new(file_name)
+    .file_stem()
+    .map_or(file_name.to_owned(), |stem| {
+      stem.to_string_lossy().into_owned()
+    })
+}
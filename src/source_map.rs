@@ -0,0 +1,112 @@
+// owns the source files being compiled, spans refer to them through a `FileId`
+
+/// Identifies a file added to a `SourceMap`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct FileId(usize);
+
+pub struct SourceFile {
+  id: FileId,
+  name: String,
+  source: String,
+  /// The byte offset every line starts at.
+  line_starts: Vec<usize>,
+}
+
+#[derive(Default)]
+pub struct SourceMap {
+  files: Vec<SourceFile>,
+}
+
+impl SourceMap {
+  pub fn new() -> Self {
+    Self::default()
+  }
+
+  /// Adds the contents of the file called `name`, returning its id.
+  pub fn add(&mut self, name: impl Into<String>, source: impl Into<String>) -> FileId {
+    let id = FileId(self.files.len());
+    self
+      .files
+      .push(SourceFile::new(id, name.into(), source.into()));
+
+    id
+  }
+
+  /// Panics if the id was handed out by another source map.
+  pub fn get(&self, id: FileId) -> &SourceFile {
+    &self.files[id.0]
+  }
+
+  pub fn files(&self) -> impl Iterator<Item = &SourceFile> {
+    self.files.iter()
+  }
+}
+
+impl SourceFile {
+  fn new(id: FileId, name: String, source: String) -> Self {
+    let line_starts = std::iter::once(0)
+      .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+      .collect();
+
+    Self {
+      id,
+      name,
+      source,
+      line_starts,
+    }
+  }
+
+  pub fn id(&self) -> FileId {
+    self.id
+  }
+
+  pub fn name(&self) -> &str {
+    &self.name
+  }
+
+  pub fn source(&self) -> &str {
+    &self.source
+  }
+
+  /// Returns the offset clamped to the source and moved back to the start of
+  /// the character it points into.
+  pub fn clamp(&self, offset: usize) -> usize {
+    let mut offset = offset.min(self.source.len());
+    while !self.source.is_char_boundary(offset) {
+      offset -= 1;
+    }
+
+    offset
+  }
+
+  /// Returns the zero based index of the line containing the offset.
+  pub fn line_index(&self, offset: usize) -> usize {
+    self.line_starts.partition_point(|start| *start <= offset) - 1
+  }
+
+  /// Returns the byte offset the line starts at.
+  pub fn line_start(&self, index: usize) -> usize {
+    self.line_starts[index]
+  }
+
+  /// Returns the line without its line ending.
+  pub fn line(&self, index: usize) -> &str {
+    let start = self.line_starts[index];
+    let end = self
+      .line_starts
+      .get(index + 1)
+      .map_or(self.source.len(), |next| next - 1);
+
+    self.source[start..end].trim_end_matches('\r')
+  }
+
+  /// Returns the zero based line and the one based column, counted in
+  /// characters, of the offset.
+  pub fn location(&self, offset: usize) -> (usize, usize) {
+    let offset = self.clamp(offset);
+    let line = self.line_index(offset);
+    let column = self.source[self.line_starts[line]..offset].chars().count() + 1;
+
+    (line, column)
+  }
+}